@@ -0,0 +1,304 @@
+//! Python bindings for the shortest path and portrait engine, so notebooks get numpy arrays
+//! back without running the binary and reading its csv output.
+//!
+//! Graphs are given as an edge list: either a numpy array with a row of source, target and
+//! optional weight per edge, or networkx-style `(u, v)`, `(u, v, weight)` or
+//! `(u, v, {"weight": weight})` tuples such as `G.edges(data=True)`.
+
+use std::num::NonZeroUsize;
+
+use numpy::{Element, PyArray1, PyArray2, PyArrayMethods, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+
+use rust_shortest_path::{
+    portrait_divergence, shared_bin_edges, Algorithm, AllPairs, Binning, Error, Graph,
+    GraphBuilder, Portrait,
+};
+
+fn value_error(error: Error) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+fn algorithm(name: &str) -> PyResult<Algorithm> {
+    name.to_ascii_lowercase()
+        .parse()
+        .map_err(PyValueError::new_err)
+}
+
+fn binning(name: &str) -> PyResult<Binning> {
+    name.to_ascii_lowercase()
+        .parse()
+        .map_err(PyValueError::new_err)
+}
+
+// Labels are normalized by the builder, so a float array's `3.0` and a networkx graph's `3.0`
+// are both the node `3` of an integer array. Integer arrays keep their own element type, as
+// ids above 2^53 don't survive a float.
+fn add_array_edges<T: Element + Copy + ToString>(
+    builder: &mut GraphBuilder,
+    edges: PyReadonlyArray2<T>,
+    weight: fn(T) -> f32,
+) -> PyResult<()> {
+    let edges = edges.as_array();
+    if edges.ncols() != 2 && edges.ncols() != 3 {
+        return Err(PyValueError::new_err(format!(
+            "edge arrays need 2 or 3 columns, not {}",
+            edges.ncols()
+        )));
+    }
+    for row in edges.rows() {
+        let weight = row.get(2).map_or(1.0, |&value| weight(value));
+        builder
+            .add_edge(&row[0].to_string(), &row[1].to_string(), weight)
+            .map_err(value_error)?;
+    }
+    Ok(())
+}
+
+fn edge_weight(data: Option<Bound<'_, PyAny>>) -> PyResult<f32> {
+    let data = match data {
+        Some(data) => data,
+        None => return Ok(1.0),
+    };
+    let weight = match data.downcast::<PyDict>() {
+        Ok(attributes) => attributes.get_item("weight")?,
+        Err(_) => Some(data.clone()),
+    };
+    match weight {
+        Some(weight) if !weight.is_none() => weight.extract(),
+        _ => Ok(1.0),
+    }
+}
+
+fn add_edges(builder: &mut GraphBuilder, edges: &Bound<'_, PyAny>) -> PyResult<()> {
+    let numpy = edges.py().import_bound("numpy")?;
+    if edges.is_instance(&numpy.getattr("ndarray")?)? {
+        let kind: String = edges.getattr("dtype")?.getattr("kind")?.extract()?;
+        return match kind.as_str() {
+            "i" => {
+                let edges = numpy.call_method1("asarray", (edges, "int64"))?;
+                add_array_edges(builder, edges.extract()?, |weight: i64| weight as f32)
+            }
+            "u" => {
+                let edges = numpy.call_method1("asarray", (edges, "uint64"))?;
+                add_array_edges(builder, edges.extract()?, |weight: u64| weight as f32)
+            }
+            _ => {
+                let edges = numpy.call_method1("asarray", (edges, "float64"))?;
+                add_array_edges(builder, edges.extract()?, |weight: f64| weight as f32)
+            }
+        };
+    }
+    for edge in edges.iter()? {
+        let edge = edge?;
+        let edge = edge.downcast::<PyTuple>()?;
+        if edge.len() != 2 && edge.len() != 3 {
+            return Err(PyValueError::new_err(format!(
+                "edges need 2 or 3 items, not {}",
+                edge.len()
+            )));
+        }
+        let src = edge.get_item(0)?.str()?;
+        let dst = edge.get_item(1)?.str()?;
+        let weight = edge_weight(edge.get_item(2).ok())?;
+        builder
+            .add_edge(src.to_str()?, dst.to_str()?, weight)
+            .map_err(value_error)?;
+    }
+    Ok(())
+}
+
+fn build_graph(
+    edges: &Bound<'_, PyAny>,
+    nodes: Option<&Bound<'_, PyAny>>,
+    directed: bool,
+    weight_scale: f64,
+    algorithm: Algorithm,
+) -> PyResult<Graph> {
+    let mut builder = Graph::builder()
+        .directed(directed)
+        .weight_scale(weight_scale)
+        .negative_weights(matches!(algorithm, Algorithm::Johnson | Algorithm::Auto));
+    add_edges(&mut builder, edges)?;
+    if let Some(nodes) = nodes {
+        for node in nodes.iter()? {
+            builder
+                .add_node(node?.str()?.to_str()?)
+                .map_err(value_error)?;
+        }
+    }
+    Ok(builder.build())
+}
+
+fn labels(graph: &Graph) -> Vec<String> {
+    let labels = graph.labels();
+    (0..labels.len())
+        .map(|index| labels.label(index).to_string())
+        .collect()
+}
+
+fn distance_matrix(graph: &Graph, algorithm: Algorithm, threads: usize) -> Result<Vec<f32>, Error> {
+    let backend = graph.prepare(algorithm)?;
+    let num_nodes = backend.num_nodes();
+    let mut matrix = vec![f32::INFINITY; num_nodes * num_nodes];
+    backend.shortest_paths(threads, &mut |paths| {
+        for path in paths {
+            matrix[path.src * num_nodes + path.dst] =
+                (path.length as f64 / graph.weight_scale()) as f32;
+        }
+        Ok(())
+    })?;
+    Ok(matrix)
+}
+
+fn portraits(
+    graphs: &[&Graph],
+    algorithm: Algorithm,
+    bins: Option<NonZeroUsize>,
+    binning: Binning,
+    threads: usize,
+) -> Result<Vec<Portrait>, Error> {
+    let backends: Vec<Box<dyn AllPairs>> = graphs
+        .iter()
+        .map(|graph| graph.prepare(algorithm))
+        .collect::<Result<_, _>>()?;
+    let backends: Vec<&dyn AllPairs> = backends.iter().map(|backend| &**backend).collect();
+    // portraits are only comparable when they share the same bins
+    let edges = match bins {
+        Some(bins) => Some(shared_bin_edges(&backends, bins.get(), binning, threads)?),
+        None => None,
+    };
+    graphs
+        .iter()
+        .zip(backends)
+        .map(|(graph, backend)| {
+            Portrait::of_graph(backend, edges.as_deref(), graph.weight_scale(), threads)
+        })
+        .collect()
+}
+
+fn portrait_array<'py>(
+    py: Python<'py>,
+    portrait: &Portrait,
+) -> PyResult<Bound<'py, PyArray2<u64>>> {
+    let rows: Vec<Vec<u64>> = portrait
+        .matrix()
+        .iter()
+        .map(|row| row.iter().map(|&count| count as u64).collect())
+        .collect();
+    Ok(PyArray2::from_vec2_bound(py, &rows)?)
+}
+
+/// Shortest path lengths between every pair of nodes, as a float32 matrix with `inf` for
+/// unreachable pairs, and the node labels naming its rows and columns.
+#[pyfunction]
+#[pyo3(signature = (
+    edges,
+    nodes = None,
+    directed = false,
+    weight_scale = 1.0,
+    algorithm = "fast-path",
+    threads = 1,
+))]
+fn all_pairs_path_length<'py>(
+    py: Python<'py>,
+    edges: &Bound<'py, PyAny>,
+    nodes: Option<&Bound<'py, PyAny>>,
+    directed: bool,
+    weight_scale: f64,
+    algorithm: &str,
+    threads: usize,
+) -> PyResult<(Bound<'py, PyArray2<f32>>, Vec<String>)> {
+    let algorithm = self::algorithm(algorithm)?;
+    let graph = build_graph(edges, nodes, directed, weight_scale, algorithm)?;
+    let num_nodes = graph.num_nodes();
+    let matrix = py
+        .allow_threads(|| distance_matrix(&graph, algorithm, threads.max(1)))
+        .map_err(value_error)?;
+    let matrix = PyArray1::from_vec_bound(py, matrix).reshape([num_nodes, num_nodes])?;
+    Ok((matrix, labels(&graph)))
+}
+
+/// The network portrait `B[l][k]` of a graph as a uint64 matrix. Path lengths are binned into
+/// `bins` when given.
+#[pyfunction]
+#[pyo3(signature = (
+    edges,
+    nodes = None,
+    directed = false,
+    weight_scale = 1.0,
+    algorithm = "fast-path",
+    bins = None,
+    binning = "quantile",
+    threads = 1,
+))]
+#[allow(clippy::too_many_arguments)]
+fn portrait<'py>(
+    py: Python<'py>,
+    edges: &Bound<'py, PyAny>,
+    nodes: Option<&Bound<'py, PyAny>>,
+    directed: bool,
+    weight_scale: f64,
+    algorithm: &str,
+    bins: Option<NonZeroUsize>,
+    binning: &str,
+    threads: usize,
+) -> PyResult<Bound<'py, PyArray2<u64>>> {
+    let algorithm = self::algorithm(algorithm)?;
+    let graph = build_graph(edges, nodes, directed, weight_scale, algorithm)?;
+    let binning = self::binning(binning)?;
+    let mut portraits = py
+        .allow_threads(|| portraits(&[&graph], algorithm, bins, binning, threads.max(1)))
+        .map_err(value_error)?;
+    portrait_array(py, &portraits.remove(0))
+}
+
+/// Portrait divergence between two graphs, from 0 for identical portraits to 1.
+#[pyfunction]
+#[pyo3(signature = (
+    first,
+    second,
+    first_nodes = None,
+    second_nodes = None,
+    directed = false,
+    weight_scale = 1.0,
+    algorithm = "fast-path",
+    bins = None,
+    binning = "quantile",
+    threads = 1,
+))]
+#[allow(clippy::too_many_arguments)]
+fn divergence(
+    py: Python<'_>,
+    first: &Bound<'_, PyAny>,
+    second: &Bound<'_, PyAny>,
+    first_nodes: Option<&Bound<'_, PyAny>>,
+    second_nodes: Option<&Bound<'_, PyAny>>,
+    directed: bool,
+    weight_scale: f64,
+    algorithm: &str,
+    bins: Option<NonZeroUsize>,
+    binning: &str,
+    threads: usize,
+) -> PyResult<f64> {
+    let algorithm = self::algorithm(algorithm)?;
+    let first = build_graph(first, first_nodes, directed, weight_scale, algorithm)?;
+    let second = build_graph(second, second_nodes, directed, weight_scale, algorithm)?;
+    let binning = self::binning(binning)?;
+    let portraits = py
+        .allow_threads(|| portraits(&[&first, &second], algorithm, bins, binning, threads.max(1)))
+        .map_err(value_error)?;
+    Ok(portrait_divergence(&portraits[0], &portraits[1]))
+}
+
+// Named apart from the crate it wraps, which it would shadow.
+#[pymodule]
+#[pyo3(name = "rust_shortest_path")]
+fn python_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(all_pairs_path_length, module)?)?;
+    module.add_function(wrap_pyfunction!(portrait, module)?)?;
+    module.add_function(wrap_pyfunction!(divergence, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,47 @@
+import math
+
+import numpy as np
+
+import rust_shortest_path as rsp
+
+
+def test_all_pairs_path_length():
+    matrix, labels = rsp.all_pairs_path_length(
+        [("a", "b", 1.0), ("b", "c", {"weight": 2.0})], nodes=["lonely"], algorithm="dijkstra"
+    )
+    assert labels == ["a", "b", "c", "lonely"]
+    assert matrix.shape == (4, 4)
+    assert matrix[0, 2] == 3.0
+    assert math.isinf(matrix[0, 3])
+
+
+def test_array_and_tuple_labels_agree():
+    _, array_labels = rsp.all_pairs_path_length(np.array([[1.0, 2.0], [2.0, 3.0]]))
+    _, tuple_labels = rsp.all_pairs_path_length([(1.0, 2.0), (2.0, 3.0)])
+    assert array_labels == tuple_labels == ["1", "2", "3"]
+
+
+def test_large_integer_ids_stay_distinct():
+    big = 2**53
+    _, labels = rsp.all_pairs_path_length(np.array([[big, big + 1]], dtype=np.int64))
+    assert labels == [str(big), str(big + 1)]
+    _, labels = rsp.all_pairs_path_length(np.array([[2**64 - 1, 2**64 - 2]], dtype=np.uint64))
+    assert labels == [str(2**64 - 1), str(2**64 - 2)]
+
+
+def test_portrait_and_divergence():
+    path = [(0, 1), (1, 2)]
+    triangle = [(0, 1), (1, 2), (2, 0)]
+    portrait = rsp.portrait(path)
+    assert portrait.tolist() == [[0, 3, 0], [0, 2, 1], [1, 2, 0]]
+    assert rsp.divergence(path, path) == 0.0
+    assert abs(rsp.divergence(path, triangle) - 0.3060986) < 1e-6
+
+
+def test_invalid_algorithm():
+    try:
+        rsp.all_pairs_path_length([(0, 1)], algorithm="nope")
+    except ValueError as error:
+        assert "unknown algorithm" in str(error)
+    else:
+        raise AssertionError("expected a ValueError")
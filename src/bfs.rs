@@ -0,0 +1,145 @@
+use crate::csr::Csr;
+use crate::error::Error;
+use crate::labels::IndexedEdge;
+use crate::parallel::for_each_source;
+use crate::{AllPairs, ShortestPathLength, Targets};
+
+const UNREACHED: usize = usize::MAX;
+
+/// Unweighted graph in compressed sparse row form, searched breadth first. Every edge counts
+/// as `unit_length`, whatever its weight.
+pub struct BfsGraph {
+    csr: Csr<usize>,
+    unit_length: usize,
+}
+
+/// Reusable search state, so consecutive sources don't reallocate.
+struct BfsSearch {
+    hops: Vec<usize>,
+    queue: Vec<usize>,
+}
+
+impl BfsSearch {
+    fn new(graph: &BfsGraph) -> Self {
+        BfsSearch {
+            hops: vec![UNREACHED; graph.num_nodes()],
+            queue: Vec::with_capacity(graph.num_nodes()),
+        }
+    }
+
+    /// Returns every node reachable from `src` (including itself) with its hop count. With
+    /// `targets`, only those are returned, and the search stops once all of them are reached.
+    fn hops_from(
+        &mut self,
+        graph: &BfsGraph,
+        src: usize,
+        targets: Option<&Targets>,
+    ) -> Vec<(usize, usize)> {
+        for &node in &self.queue {
+            self.hops[node] = UNREACHED;
+        }
+        self.queue.clear();
+
+        let is_target = |node| targets.is_some_and(|targets: &Targets| targets.contains(node));
+        // hop counts are final as soon as a node is reached
+        let mut unreached = targets.map_or(usize::MAX, |targets| targets.len());
+        self.hops[src] = 0;
+        self.queue.push(src);
+        if is_target(src) {
+            unreached -= 1;
+        }
+        let mut head = 0;
+        'search: while head < self.queue.len() && unreached > 0 {
+            let node = self.queue[head];
+            head += 1;
+            let next = self.hops[node] + 1;
+            for &neighbor in graph.csr.neighbors(node) {
+                if self.hops[neighbor] == UNREACHED {
+                    self.hops[neighbor] = next;
+                    self.queue.push(neighbor);
+                    if is_target(neighbor) {
+                        unreached -= 1;
+                        if unreached == 0 {
+                            break 'search;
+                        }
+                    }
+                }
+            }
+        }
+        self.queue
+            .iter()
+            .filter(|&&node| targets.is_none_or(|targets| targets.contains(node)))
+            .map(|&node| (node, self.hops[node]))
+            .collect()
+    }
+}
+
+pub(crate) fn into_bfs_graph(
+    num_nodes: usize,
+    edges: &[IndexedEdge],
+    directed: bool,
+    unit_length: usize,
+) -> BfsGraph {
+    let mut arcs = Vec::with_capacity(edges.len() * 2);
+    for edge in edges {
+        arcs.push((edge.src, edge.dst));
+        if !directed {
+            arcs.push((edge.dst, edge.src));
+        }
+    }
+    BfsGraph {
+        csr: Csr::new(num_nodes, &arcs),
+        unit_length,
+    }
+}
+
+impl AllPairs for BfsGraph {
+    fn num_nodes(&self) -> usize {
+        self.csr.num_nodes()
+    }
+
+    fn shortest_paths_from(
+        &self,
+        sources: &[usize],
+        threads: usize,
+        sink: &mut dyn FnMut(&[ShortestPathLength]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        self.search(sources, None, threads, sink)
+    }
+
+    fn shortest_paths_to(
+        &self,
+        sources: &[usize],
+        targets: &Targets,
+        threads: usize,
+        sink: &mut dyn FnMut(&[ShortestPathLength]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        self.search(sources, Some(targets), threads, sink)
+    }
+}
+
+impl BfsGraph {
+    fn search(
+        &self,
+        sources: &[usize],
+        targets: Option<&Targets>,
+        threads: usize,
+        sink: &mut dyn FnMut(&[ShortestPathLength]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        for_each_source(
+            sources,
+            threads,
+            || BfsSearch::new(self),
+            |search, src, res| {
+                for (dst, hops) in search.hops_from(self, src, targets) {
+                    res.push(ShortestPathLength {
+                        src,
+                        dst,
+                        length: (hops * self.unit_length) as i64,
+                    })
+                }
+            },
+            sink,
+        )
+    }
+}
@@ -0,0 +1,37 @@
+/// Adjacency lists in compressed sparse row form: the neighbors of node `i` are the slice
+/// `targets[offsets[i]..offsets[i + 1]]`, so searches borrow them instead of copying.
+pub(crate) struct Csr<T> {
+    offsets: Vec<usize>,
+    targets: Vec<T>,
+}
+
+impl<T: Copy + Default> Csr<T> {
+    /// Groups `arcs` of a source node and a target by source, keeping their order otherwise.
+    pub(crate) fn new(num_nodes: usize, arcs: &[(usize, T)]) -> Self {
+        let mut offsets = vec![0; num_nodes + 1];
+        for &(src, _) in arcs {
+            offsets[src + 1] += 1;
+        }
+        for node in 0..num_nodes {
+            offsets[node + 1] += offsets[node];
+        }
+
+        let mut next = offsets.clone();
+        let mut targets = vec![T::default(); arcs.len()];
+        for &(src, target) in arcs {
+            targets[next[src]] = target;
+            next[src] += 1;
+        }
+        Csr { offsets, targets }
+    }
+}
+
+impl<T> Csr<T> {
+    pub(crate) fn num_nodes(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub(crate) fn neighbors(&self, node: usize) -> &[T] {
+        &self.targets[self.offsets[node]..self.offsets[node + 1]]
+    }
+}
@@ -0,0 +1,153 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::csr::Csr;
+use crate::error::Error;
+use crate::labels::IndexedEdge;
+use crate::parallel::for_each_source;
+use crate::{scale_weight, AllPairs, ShortestPathLength, Targets};
+
+const UNREACHED: u64 = u64::MAX;
+
+/// Weighted graph searched with Dijkstra's algorithm from every source.
+pub struct DijkstraGraph {
+    csr: Csr<(usize, u64)>,
+}
+
+/// Reusable search state over non-negative weights, so consecutive sources don't reallocate.
+pub(crate) struct DijkstraSearch {
+    distances: Vec<u64>,
+    reached: Vec<usize>,
+    heap: BinaryHeap<Reverse<(u64, usize)>>,
+}
+
+impl DijkstraSearch {
+    pub(crate) fn new(num_nodes: usize) -> Self {
+        DijkstraSearch {
+            distances: vec![UNREACHED; num_nodes],
+            reached: Vec::new(),
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    /// Returns every node reachable from `src` (including itself) in index order, with its
+    /// distance. With `targets`, only those are returned, and the search stops once all of them
+    /// are settled.
+    pub(crate) fn distances_from(
+        &mut self,
+        csr: &Csr<(usize, u64)>,
+        src: usize,
+        targets: Option<&Targets>,
+    ) -> Vec<(usize, u64)> {
+        for &node in &self.reached {
+            self.distances[node] = UNREACHED;
+        }
+        self.reached.clear();
+        self.heap.clear();
+
+        let mut unsettled = targets.map_or(usize::MAX, |targets| targets.len());
+        self.distances[src] = 0;
+        self.reached.push(src);
+        self.heap.push(Reverse((0, src)));
+        while unsettled > 0 {
+            let Reverse((distance, node)) = match self.heap.pop() {
+                Some(entry) => entry,
+                None => break,
+            };
+            if distance > self.distances[node] {
+                continue;
+            }
+            if targets.is_some_and(|targets| targets.contains(node)) {
+                unsettled -= 1;
+            }
+            for &(neighbor, weight) in csr.neighbors(node) {
+                let next = distance + weight;
+                if next < self.distances[neighbor] {
+                    if self.distances[neighbor] == UNREACHED {
+                        self.reached.push(neighbor);
+                    }
+                    self.distances[neighbor] = next;
+                    self.heap.push(Reverse((next, neighbor)));
+                }
+            }
+        }
+        self.reached.sort_unstable();
+        self.reached
+            .iter()
+            // nodes left unsettled by an early stop aren't targets, so their distances are dropped
+            .filter(|&&node| targets.is_none_or(|targets| targets.contains(node)))
+            .map(|&node| (node, self.distances[node]))
+            .collect()
+    }
+}
+
+pub(crate) fn into_dijkstra_graph(
+    num_nodes: usize,
+    edges: &[IndexedEdge],
+    weight_scale: f64,
+    directed: bool,
+) -> DijkstraGraph {
+    let mut arcs = Vec::with_capacity(edges.len() * 2);
+    for edge in edges {
+        let weight = scale_weight(edge.weight, weight_scale) as u64;
+        arcs.push((edge.src, (edge.dst, weight)));
+        if !directed {
+            arcs.push((edge.dst, (edge.src, weight)));
+        }
+    }
+    DijkstraGraph {
+        csr: Csr::new(num_nodes, &arcs),
+    }
+}
+
+impl AllPairs for DijkstraGraph {
+    /// Counts every node of the graph, including isolated ones without successors.
+    fn num_nodes(&self) -> usize {
+        self.csr.num_nodes()
+    }
+
+    fn shortest_paths_from(
+        &self,
+        sources: &[usize],
+        threads: usize,
+        sink: &mut dyn FnMut(&[ShortestPathLength]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        self.search(sources, None, threads, sink)
+    }
+
+    fn shortest_paths_to(
+        &self,
+        sources: &[usize],
+        targets: &Targets,
+        threads: usize,
+        sink: &mut dyn FnMut(&[ShortestPathLength]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        self.search(sources, Some(targets), threads, sink)
+    }
+}
+
+impl DijkstraGraph {
+    fn search(
+        &self,
+        sources: &[usize],
+        targets: Option<&Targets>,
+        threads: usize,
+        sink: &mut dyn FnMut(&[ShortestPathLength]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        for_each_source(
+            sources,
+            threads,
+            || DijkstraSearch::new(self.num_nodes()),
+            |search, src, res| {
+                for (dst, distance) in search.distances_from(&self.csr, src, targets) {
+                    res.push(ShortestPathLength {
+                        src,
+                        dst,
+                        length: distance as i64,
+                    })
+                }
+            },
+            sink,
+        )
+    }
+}
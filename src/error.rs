@@ -0,0 +1,134 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    Parse {
+        path: String,
+        line: Option<u64>,
+        column: Option<u64>,
+        message: String,
+    },
+    InvalidWeight {
+        path: String,
+        line: u64,
+        weight: f32,
+        reason: String,
+    },
+    NodeIdOverflow {
+        path: String,
+        line: u64,
+        label: String,
+    },
+    /// Options or arguments that don't make sense together
+    Invalid(String),
+    /// A search thread panicked with this message
+    Worker(String),
+}
+
+impl Error {
+    /// Wraps an error from reading or writing the CSV file at `path`. `line` is used when the
+    /// error itself doesn't know where it happened, e.g. when deserializing a record.
+    pub(crate) fn csv(path: &str, line: Option<u64>, error: csv::Error) -> Self {
+        let line = error.position().map(|position| position.line()).or(line);
+        let path = path.to_string();
+        match error.into_kind() {
+            csv::ErrorKind::Io(source) => Error::Io { path, source },
+            csv::ErrorKind::Deserialize { err, .. } => Error::Parse {
+                path,
+                line,
+                column: err.field().map(|field| field + 1),
+                message: err.kind().to_string(),
+            },
+            csv::ErrorKind::UnequalLengths {
+                expected_len, len, ..
+            } => Error::Parse {
+                path,
+                line,
+                column: None,
+                message: format!("expected {} fields, found {}", expected_len, len),
+            },
+            csv::ErrorKind::Utf8 { err, .. } => Error::Parse {
+                path,
+                line,
+                column: Some(err.field() as u64 + 1),
+                message: "invalid UTF-8".to_string(),
+            },
+            kind => Error::Parse {
+                path,
+                line,
+                column: None,
+                message: format!("{:?}", kind),
+            },
+        }
+    }
+
+    pub(crate) fn parse(path: &str, line: u64, message: String) -> Self {
+        Error::Parse {
+            path: path.to_string(),
+            line: Some(line),
+            column: None,
+            message,
+        }
+    }
+
+    pub(crate) fn io(path: &str, source: io::Error) -> Self {
+        Error::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path, source),
+            Error::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => {
+                write!(f, "{}", path)?;
+                if let Some(line) = line {
+                    write!(f, ":{}", line)?;
+                }
+                if let Some(column) = column {
+                    write!(f, ": column {}", column)?;
+                }
+                write!(f, ": {}", message)
+            }
+            Error::InvalidWeight {
+                path,
+                line,
+                weight,
+                reason,
+            } => write!(
+                f,
+                "{}:{}: invalid weight {}: {}",
+                path, line, weight, reason
+            ),
+            Error::NodeIdOverflow { path, line, label } => write!(
+                f,
+                "{}:{}: node id {} does not fit in a 64-bit integer",
+                path, line, label
+            ),
+            Error::Invalid(message) => write!(f, "{}", message),
+            Error::Worker(message) => write!(f, "search thread failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
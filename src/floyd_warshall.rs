@@ -0,0 +1,188 @@
+use std::ops::Range;
+
+use crate::error::Error;
+use crate::labels::IndexedEdge;
+use crate::parallel::for_each_source;
+use crate::{scale_weight, AllPairs, ShortestPathLength};
+
+// Half the range, so adding two lengths can't overflow: real paths stay far below it, as
+// weights are at most 2^32.
+const UNREACHED: u64 = u64::MAX / 2;
+
+// Nodes per side of the blocks the matrix is updated in, small enough for three blocks to stay
+// in cache.
+const BLOCK: usize = 64;
+
+/// Dense matrix of every shortest path length, computed up front with the Floyd-Warshall
+/// algorithm. Takes `num_nodes^2` space, so it suits small graphs.
+pub struct FloydWarshallGraph {
+    num_nodes: usize,
+    distances: Vec<u64>,
+}
+
+// Relaxes the paths from nodes `rows` to nodes `columns` through each node of `through`.
+fn relax(
+    distances: &mut [u64],
+    num_nodes: usize,
+    through: Range<usize>,
+    rows: Range<usize>,
+    columns: Range<usize>,
+) {
+    for k in through {
+        for i in rows.clone() {
+            let via = distances[i * num_nodes + k];
+            if via >= UNREACHED {
+                continue;
+            }
+            for j in columns.clone() {
+                let length = via + distances[k * num_nodes + j];
+                if length < distances[i * num_nodes + j] {
+                    distances[i * num_nodes + j] = length;
+                }
+            }
+        }
+    }
+}
+
+// Blocked Floyd-Warshall: for each block of intermediate nodes, first the block on the
+// diagonal, then the blocks sharing its rows or columns, which depend on it, then the rest.
+fn floyd_warshall(distances: &mut [u64], num_nodes: usize) {
+    let blocks: Vec<Range<usize>> = (0..num_nodes)
+        .step_by(BLOCK)
+        .map(|start| start..(start + BLOCK).min(num_nodes))
+        .collect();
+    for through in &blocks {
+        relax(
+            distances,
+            num_nodes,
+            through.clone(),
+            through.clone(),
+            through.clone(),
+        );
+        for block in blocks.iter().filter(|&block| block != through) {
+            relax(
+                distances,
+                num_nodes,
+                through.clone(),
+                through.clone(),
+                block.clone(),
+            );
+            relax(
+                distances,
+                num_nodes,
+                through.clone(),
+                block.clone(),
+                through.clone(),
+            );
+        }
+        for rows in blocks.iter().filter(|&block| block != through) {
+            for columns in blocks.iter().filter(|&block| block != through) {
+                relax(
+                    distances,
+                    num_nodes,
+                    through.clone(),
+                    rows.clone(),
+                    columns.clone(),
+                );
+            }
+        }
+    }
+}
+
+pub(crate) fn into_floyd_warshall_graph(
+    num_nodes: usize,
+    edges: &[IndexedEdge],
+    weight_scale: f64,
+    directed: bool,
+) -> FloydWarshallGraph {
+    let mut distances = vec![UNREACHED; num_nodes * num_nodes];
+    for node in 0..num_nodes {
+        distances[node * num_nodes + node] = 0;
+    }
+    let mut insert = |src: usize, dst: usize, weight: u64| {
+        let distance = &mut distances[src * num_nodes + dst];
+        *distance = (*distance).min(weight);
+    };
+    for edge in edges {
+        let weight = scale_weight(edge.weight, weight_scale) as u64;
+        insert(edge.src, edge.dst, weight);
+        if !directed {
+            insert(edge.dst, edge.src, weight);
+        }
+    }
+    floyd_warshall(&mut distances, num_nodes);
+    FloydWarshallGraph {
+        num_nodes,
+        distances,
+    }
+}
+
+impl AllPairs for FloydWarshallGraph {
+    fn num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    fn shortest_paths_from(
+        &self,
+        sources: &[usize],
+        threads: usize,
+        sink: &mut dyn FnMut(&[ShortestPathLength]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        for_each_source(
+            sources,
+            threads,
+            || (),
+            |_, src, res| {
+                let row = &self.distances[src * self.num_nodes..(src + 1) * self.num_nodes];
+                for (dst, &length) in row.iter().enumerate() {
+                    if length < UNREACHED {
+                        res.push(ShortestPathLength {
+                            src,
+                            dst,
+                            length: length as i64,
+                        })
+                    }
+                }
+            },
+            sink,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::subset::SplitMix64;
+    use crate::tests::lengths;
+    use crate::Graph;
+
+    // Spans several blocks, the last one partial.
+    #[test]
+    fn same_lengths_as_dijkstra() {
+        let mut rng = SplitMix64(3);
+        for directed in [false, true] {
+            let mut builder = Graph::builder().directed(directed);
+            for _ in 0..400 {
+                let src = rng.below(150).to_string();
+                let dst = rng.below(150).to_string();
+                let weight = (1 + rng.below(50)) as f32;
+                builder.add_edge(&src, &dst, weight).unwrap();
+            }
+            let graph = builder.build();
+            assert!(graph.num_nodes() > 2 * super::BLOCK);
+            assert_eq!(
+                lengths(&graph.floyd_warshall().unwrap()),
+                lengths(&graph.dijkstra().unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn too_many_nodes() {
+        let mut builder = Graph::builder();
+        for node in 0..=crate::MAX_FLOYD_WARSHALL_MATRIX_NODES {
+            builder.add_node(&node.to_string()).unwrap();
+        }
+        let graph = builder.build();
+        assert!(graph.floyd_warshall().is_err());
+    }
+}
@@ -0,0 +1,491 @@
+use std::cell::RefCell;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use csv::StringRecord;
+
+use crate::error::Error;
+use crate::labels::normalize_label;
+use crate::stream;
+use crate::{check_weight, NodeLabel, WeightedNodes};
+
+mod gml;
+mod graphml;
+mod matrix_market;
+mod pajek;
+
+named_enum! {
+    #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+    pub enum InputFormat("input format") {
+        Csv = "csv",
+        Graphml = "graphml",
+        Gml = "gml",
+        Pajek = "pajek",
+        MatrixMarket = "matrix-market",
+    }
+}
+
+impl InputFormat {
+    // Anything without a known graph extension is read as an edge list.
+    fn from_path(path: &str) -> Self {
+        let extension = Path::new(stream::uncompressed_path(path))
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("graphml") => InputFormat::Graphml,
+            Some("gml") => InputFormat::Gml,
+            Some("net") | Some("paj") => InputFormat::Pajek,
+            Some("mtx") => InputFormat::MatrixMarket,
+            _ => InputFormat::Csv,
+        }
+    }
+}
+
+named_enum! {
+    #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+    pub enum Delimiter("delimiter") {
+        Comma = "comma",
+        Tab = "tab",
+        Space = "space",
+        Semicolon = "semicolon",
+        /// Any run of spaces and tabs, as in SNAP and KONECT edge lists
+        Whitespace = "whitespace",
+    }
+}
+
+/// A column picked either by its 0-based position or by its name in the header row.
+#[derive(Clone, Debug)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse() {
+            Ok(index) => Ok(Column::Index(index)),
+            Err(_) => Ok(Column::Name(s.to_string())),
+        }
+    }
+}
+
+/// How to read graph files, and edge lists in particular.
+#[derive(Clone, Debug)]
+pub struct EdgeListOptions {
+    /// Format of the graph files, guessed from their extension when `None`
+    pub input_format: Option<InputFormat>,
+    /// Treat the first row of edge lists as a header instead of an edge
+    pub has_header: bool,
+    pub source_column: Column,
+    pub target_column: Column,
+    pub weight_column: Column,
+    /// Weight of edges that don't have one
+    pub default_weight: f32,
+    /// Give every edge the default weight, whatever the file says
+    pub ignore_weights: bool,
+    pub delimiter: Delimiter,
+    /// Skip lines starting with this character
+    pub comment: Option<char>,
+}
+
+impl Default for EdgeListOptions {
+    fn default() -> Self {
+        EdgeListOptions {
+            input_format: None,
+            has_header: false,
+            source_column: Column::Index(0),
+            target_column: Column::Index(1),
+            weight_column: Column::Index(2),
+            default_weight: 1.0,
+            ignore_weights: false,
+            delimiter: Delimiter::Comma,
+            comment: None,
+        }
+    }
+}
+
+/// The edges and declared nodes of a graph file. Readers hand over labels and weights as
+/// they are parsed, so they are checked against the line they came from.
+pub(crate) struct EdgeList<'a> {
+    pub(crate) edges: Vec<WeightedNodes>,
+    /// Nodes the file declares, including ones without edges
+    pub(crate) nodes: Vec<String>,
+    path: &'a str,
+    args: &'a EdgeListOptions,
+    directed: bool,
+    weight_scale: f64,
+}
+
+impl<'a> EdgeList<'a> {
+    fn new(path: &'a str, args: &'a EdgeListOptions, directed: bool, weight_scale: f64) -> Self {
+        EdgeList {
+            edges: Vec::new(),
+            nodes: Vec::new(),
+            path,
+            args,
+            directed,
+            weight_scale,
+        }
+    }
+
+    /// Parses a weight from the file, or returns `None` when weights are ignored.
+    pub(crate) fn weight(
+        &self,
+        line: u64,
+        column: Option<u64>,
+        weight: &str,
+    ) -> Result<Option<f32>, Error> {
+        if self.args.ignore_weights {
+            return Ok(None);
+        }
+        match weight.trim().parse() {
+            Ok(weight) => Ok(Some(weight)),
+            Err(_) => Err(Error::Parse {
+                path: self.path.to_string(),
+                line: Some(line),
+                column,
+                message: format!("invalid weight {:?}", weight),
+            }),
+        }
+    }
+
+    fn label(&self, line: u64, label: String) -> Result<String, Error> {
+        normalize_label(label).map_err(|label| Error::NodeIdOverflow {
+            path: self.path.to_string(),
+            line,
+            label,
+        })
+    }
+
+    pub(crate) fn declare(&mut self, line: u64, label: String) -> Result<(), Error> {
+        let label = self.label(line, label)?;
+        self.nodes.push(label);
+        Ok(())
+    }
+
+    /// Adds an edge, with the default weight when `weight` is `None`.
+    pub(crate) fn push(
+        &mut self,
+        line: u64,
+        src: String,
+        dst: String,
+        weight: Option<f32>,
+    ) -> Result<(), Error> {
+        let weight = match self.args.ignore_weights {
+            true => self.args.default_weight,
+            false => weight.unwrap_or(self.args.default_weight),
+        };
+        check_weight(weight, self.weight_scale).map_err(|reason| Error::InvalidWeight {
+            path: self.path.to_string(),
+            line,
+            weight,
+            reason,
+        })?;
+        let src = self.label(line, src)?;
+        let dst = self.label(line, dst)?;
+        self.edges.push(WeightedNodes {
+            src,
+            dst,
+            weight,
+            line,
+        });
+        Ok(())
+    }
+
+    /// Adds an edge the file marks as undirected, which is also added backwards when the
+    /// graph is directed.
+    pub(crate) fn push_undirected(
+        &mut self,
+        line: u64,
+        src: String,
+        dst: String,
+        weight: Option<f32>,
+    ) -> Result<(), Error> {
+        if self.directed && src != dst {
+            self.push(line, src.clone(), dst.clone(), weight)?;
+            return self.push(line, dst, src, weight);
+        }
+        self.push(line, src, dst, weight)
+    }
+}
+
+/// Parses one graph file format into an edge list.
+pub(crate) trait GraphReader {
+    fn read(&self, path: &str, edges: &mut EdgeList) -> Result<(), Error>;
+}
+
+struct CsvReader<'a>(&'a EdgeListOptions);
+
+fn graph_reader<'a>(path: &str, args: &'a EdgeListOptions) -> Box<dyn GraphReader + 'a> {
+    match args
+        .input_format
+        .unwrap_or_else(|| InputFormat::from_path(path))
+    {
+        InputFormat::Csv => Box::new(CsvReader(args)),
+        InputFormat::Graphml => Box::new(graphml::GraphmlReader),
+        InputFormat::Gml => Box::new(gml::GmlReader),
+        InputFormat::Pajek => Box::new(pajek::PajekReader),
+        InputFormat::MatrixMarket => Box::new(matrix_market::MatrixMarketReader),
+    }
+}
+
+/// Reads the graph file at `path` in the format given on the command line or guessed from
+/// its extension.
+pub(crate) fn read_edge_list<'a>(
+    path: &'a str,
+    args: &'a EdgeListOptions,
+    directed: bool,
+    weight_scale: f64,
+) -> Result<EdgeList<'a>, Error> {
+    let mut edges = EdgeList::new(path, args, directed, weight_scale);
+    graph_reader(path, args).read(path, &mut edges)?;
+    Ok(edges)
+}
+
+type Records = Box<dyn Iterator<Item = Result<(u64, StringRecord), Error>>>;
+
+// Every record with the line it starts on, skipping blank and comment lines.
+fn records(path: &str, args: &EdgeListOptions) -> Result<Records, Error> {
+    let file = stream::open(path).map_err(|e| Error::io(path, e))?;
+    let delimiter = match args.delimiter {
+        Delimiter::Comma => b',',
+        Delimiter::Tab => b'\t',
+        Delimiter::Space => b' ',
+        Delimiter::Semicolon => b';',
+        Delimiter::Whitespace => return Ok(whitespace_records(path, file, args.comment)),
+    };
+    // csv only takes single byte comments
+    let comment = args.comment.map(|comment| {
+        u8::try_from(comment)
+            .ok()
+            .filter(u8::is_ascii)
+            .ok_or_else(|| {
+                Error::Invalid(format!(
+                    "the comment {:?} must be an ASCII character",
+                    comment
+                ))
+            })
+    });
+    let comment = comment.transpose()?;
+    let seen = Rc::new(RefCell::new(SeenBytes::default()));
+    let reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .comment(comment)
+        .from_reader(Tee {
+            inner: file,
+            seen: Rc::clone(&seen),
+        });
+    Ok(Box::new(CsvRecords {
+        path: path.to_string(),
+        reader,
+        seen,
+        comment,
+    }))
+}
+
+// Runs of whitespace can't be a csv delimiter, so those lines are split one at a time.
+fn whitespace_records(path: &str, file: Box<dyn Read>, comment: Option<char>) -> Records {
+    let path = path.to_string();
+    let lines = BufReader::new(file).lines().zip(1..);
+    Box::new(lines.filter_map(move |(line, number)| {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => return Some(Err(Error::io(&path, e))),
+        };
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || comment.is_some_and(|comment| trimmed.starts_with(comment)) {
+            return None;
+        }
+        let record = StringRecord::from(line.split_whitespace().collect::<Vec<_>>());
+        Some(Ok((number, record)))
+    }))
+}
+
+// The bytes of the input from `offset` on, read by csv but not yet looked at for line numbers.
+#[derive(Default)]
+struct SeenBytes {
+    offset: u64,
+    bytes: Vec<u8>,
+}
+
+// Keeps a copy of what the csv reader reads, in `seen`.
+struct Tee {
+    inner: Box<dyn Read>,
+    seen: Rc<RefCell<SeenBytes>>,
+}
+
+impl Read for Tee {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.seen.borrow_mut().bytes.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+}
+
+struct CsvRecords {
+    path: String,
+    reader: csv::Reader<Tee>,
+    seen: Rc<RefCell<SeenBytes>>,
+    comment: Option<u8>,
+}
+
+impl CsvRecords {
+    // csv positions a record where it started looking for it, before the blank and comment
+    // lines it skipped, so those are counted from the bytes it read.
+    fn start_line(&self, position: &csv::Position) -> u64 {
+        let seen = self.seen.borrow();
+        let mut line = position.line();
+        let mut rest = &seen.bytes[(position.byte() - seen.offset) as usize..];
+        while let Some(end) = rest.iter().position(|&byte| byte == b'\n') {
+            let content = &rest[..end];
+            let skipped = content.iter().all(u8::is_ascii_whitespace)
+                || self
+                    .comment
+                    .is_some_and(|comment| content.first() == Some(&comment));
+            if !skipped {
+                break;
+            }
+            line += 1;
+            rest = &rest[end + 1..];
+        }
+        line
+    }
+
+    // Forgets the bytes before the next record, once they are worth moving the rest for.
+    fn forget_read(&self) {
+        let mut seen = self.seen.borrow_mut();
+        let read = (self.reader.position().byte() - seen.offset) as usize;
+        if read >= 64 * 1024 && read * 2 >= seen.bytes.len() {
+            seen.bytes.drain(..read);
+            seen.offset += read as u64;
+        }
+    }
+}
+
+impl Iterator for CsvRecords {
+    type Item = Result<(u64, StringRecord), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = StringRecord::new();
+        loop {
+            match self.reader.read_record(&mut record) {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => return Some(Err(Error::csv(&self.path, None, e))),
+            }
+            let line = record
+                .position()
+                .map_or(0, |position| self.start_line(position));
+            self.forget_read();
+            // lines of only spaces are records to csv
+            let blank = record.len() == 1 && record[0].trim().is_empty();
+            if !blank {
+                return Some(Ok((line, record)));
+            }
+        }
+    }
+}
+
+fn resolve(column: &Column, header: Option<&StringRecord>, path: &str) -> Result<usize, Error> {
+    let name = match column {
+        Column::Index(index) => return Ok(*index),
+        Column::Name(name) => name,
+    };
+    let message = match header {
+        Some(header) => match header.iter().position(|field| field.trim() == name) {
+            Some(index) => return Ok(index),
+            None => format!("no column named {:?} in the header", name),
+        },
+        None => format!(
+            "column {:?} is selected by name, which needs --has-header",
+            name
+        ),
+    };
+    Err(Error::Parse {
+        path: path.to_string(),
+        line: header.map(|_| 1),
+        column: None,
+        message,
+    })
+}
+
+fn field<'a>(
+    record: &'a StringRecord,
+    column: usize,
+    path: &str,
+    line: u64,
+) -> Result<&'a str, Error> {
+    record
+        .get(column)
+        .map(str::trim)
+        .ok_or_else(|| Error::Parse {
+            path: path.to_string(),
+            line: Some(line),
+            column: Some(column as u64 + 1),
+            message: format!(
+                "expected at least {} fields, found {}",
+                column + 1,
+                record.len()
+            ),
+        })
+}
+
+impl GraphReader for CsvReader<'_> {
+    fn read(&self, path: &str, edges: &mut EdgeList) -> Result<(), Error> {
+        let args = self.0;
+        let mut records = records(path, args)?;
+        let header = match args.has_header {
+            true => records.next().transpose()?.map(|(_, header)| header),
+            false => None,
+        };
+        let src_column = resolve(&args.source_column, header.as_ref(), path)?;
+        let dst_column = resolve(&args.target_column, header.as_ref(), path)?;
+        let weight_column = match args.ignore_weights {
+            true => None,
+            false => Some(resolve(&args.weight_column, header.as_ref(), path)?),
+        };
+
+        for record in records {
+            let (line, record) = record?;
+            let weight = match weight_column.and_then(|column| Some((column, record.get(column)?)))
+            {
+                Some((column, weight)) => edges.weight(line, Some(column as u64 + 1), weight)?,
+                None => None,
+            };
+            let src = field(&record, src_column, path, line)?.to_string();
+            let dst = field(&record, dst_column, path, line)?.to_string();
+            edges.push(line, src, dst, weight)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn read_node_labels(path: &str) -> Result<Vec<NodeLabel>, Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(stream::open(path).map_err(|e| Error::io(path, e))?);
+
+    let mut nodes = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| Error::csv(path, None, e))?;
+        let line = record.position().map_or(0, |position| position.line());
+        let node: NodeLabel = record
+            .deserialize(None)
+            .map_err(|e| Error::csv(path, Some(line), e))?;
+        let label = normalize_label(node.label).map_err(|label| Error::NodeIdOverflow {
+            path: path.to_string(),
+            line,
+            label,
+        })?;
+        nodes.push(NodeLabel { label });
+    }
+    Ok(nodes)
+}
@@ -0,0 +1,160 @@
+use crate::error::Error;
+use crate::input::{EdgeList, GraphReader};
+use crate::stream;
+use std::collections::HashMap;
+
+/// Reads a GML file. Nodes are labelled by their `label` when they have one and by their `id`
+/// otherwise, and edge weights come from a `weight` or `value` key. Edges are undirected unless
+/// the graph says `directed 1`.
+pub(crate) struct GmlReader;
+
+enum Value {
+    Atom(String),
+    List(Vec<Entry>),
+}
+
+struct Entry {
+    key: String,
+    value: Value,
+    line: u64,
+}
+
+struct Parser<'a> {
+    rest: &'a str,
+    line: u64,
+    path: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        loop {
+            let trimmed = self.rest.trim_start();
+            self.line += self.rest[..self.rest.len() - trimmed.len()]
+                .matches('\n')
+                .count() as u64;
+            self.rest = trimmed;
+            match self.rest.starts_with('#') {
+                true => self.rest = &self.rest[self.rest.find('\n').unwrap_or(self.rest.len())..],
+                false => return,
+            }
+        }
+    }
+
+    fn token(&mut self) -> Result<&'a str, Error> {
+        self.skip_whitespace();
+        let len = match self.rest.strip_prefix('"') {
+            Some(quoted) => match quoted.find('"') {
+                Some(end) => end + 2,
+                None => {
+                    return Err(Error::parse(
+                        self.path,
+                        self.line,
+                        "unterminated string".to_string(),
+                    ))
+                }
+            },
+            None if self.rest.starts_with(['[', ']']) => 1,
+            None => self
+                .rest
+                .find(|c: char| c.is_whitespace() || c == '[' || c == ']')
+                .unwrap_or(self.rest.len()),
+        };
+        let (token, rest) = self.rest.split_at(len);
+        self.line += token.matches('\n').count() as u64;
+        self.rest = rest;
+        Ok(token)
+    }
+
+    // Parses `key value` pairs up to a closing bracket, or the end of the file at the top level.
+    fn list(&mut self, nested: bool) -> Result<Vec<Entry>, Error> {
+        let mut entries = Vec::new();
+        loop {
+            let line = self.line;
+            let key = match self.token()? {
+                "" if !nested => return Ok(entries),
+                "]" if nested => return Ok(entries),
+                "" => return Err(Error::parse(self.path, line, "unclosed [".to_string())),
+                key if key.starts_with(['[', ']', '"']) => {
+                    return Err(Error::parse(
+                        self.path,
+                        line,
+                        format!("expected a key, found {}", key),
+                    ))
+                }
+                key => key.to_string(),
+            };
+            let line = self.line;
+            let value = match self.token()? {
+                "[" => Value::List(self.list(true)?),
+                "" | "]" => {
+                    return Err(Error::parse(
+                        self.path,
+                        line,
+                        format!("missing value for {}", key),
+                    ))
+                }
+                atom => Value::Atom(atom.trim_matches('"').to_string()),
+            };
+            entries.push(Entry { key, value, line });
+        }
+    }
+}
+
+fn atom<'a>(entries: &'a [Entry], key: &str) -> Option<&'a str> {
+    entries.iter().find_map(|entry| match &entry.value {
+        Value::Atom(atom) if entry.key == key => Some(atom.as_str()),
+        _ => None,
+    })
+}
+
+fn lists<'a>(entries: &'a [Entry], key: &'a str) -> impl Iterator<Item = (&'a [Entry], u64)> {
+    entries.iter().filter_map(move |entry| match &entry.value {
+        Value::List(list) if entry.key == key => Some((list.as_slice(), entry.line)),
+        _ => None,
+    })
+}
+
+impl GraphReader for GmlReader {
+    fn read(&self, path: &str, edges: &mut EdgeList) -> Result<(), Error> {
+        let text = stream::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        let mut parser = Parser {
+            rest: &text,
+            line: 1,
+            path,
+        };
+        let entries = parser.list(false)?;
+        let (graph, _) = lists(&entries, "graph")
+            .next()
+            .ok_or_else(|| Error::parse(path, 1, "missing graph [ ... ]".to_string()))?;
+        let directed = atom(graph, "directed") == Some("1");
+
+        let mut labels = HashMap::new();
+        for (node, line) in lists(graph, "node") {
+            let id = atom(node, "id")
+                .ok_or_else(|| Error::parse(path, line, "node without an id".to_string()))?;
+            let label = atom(node, "label").unwrap_or(id);
+            labels.insert(id, label);
+            edges.declare(line, label.to_string())?;
+        }
+        for (edge, line) in lists(graph, "edge") {
+            let node = |key| {
+                let id = atom(edge, key)
+                    .ok_or_else(|| Error::parse(path, line, format!("edge without a {}", key)))?;
+                labels
+                    .get(id)
+                    .map(|label| label.to_string())
+                    .ok_or_else(|| Error::parse(path, line, format!("edge to unknown node {}", id)))
+            };
+            let (src, dst) = (node("source")?, node("target")?);
+            let weight = match atom(edge, "weight").or_else(|| atom(edge, "value")) {
+                Some(weight) => edges.weight(line, None, weight)?,
+                None => None,
+            };
+            match directed {
+                true => edges.push(line, src, dst, weight)?,
+                false => edges.push_undirected(line, src, dst, weight)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::error::Error;
+use crate::input::{EdgeList, GraphReader};
+use crate::stream;
+
+/// Reads a GraphML file. Nodes are labelled by their `id`, and edge weights come from the data
+/// key whose `attr.name` is `weight`, falling back on that key's default. Edges follow the
+/// graph's `edgedefault` unless they set `directed` themselves.
+pub(crate) struct GraphmlReader;
+
+// Line numbers of byte positions, counted as the reader moves forward through `text`.
+struct Lines<'a> {
+    text: &'a str,
+    position: usize,
+    line: u64,
+}
+
+impl Lines<'_> {
+    fn at(&mut self, position: u64) -> u64 {
+        let position = (position as usize).clamp(self.position, self.text.len());
+        self.line += self.text.as_bytes()[self.position..position]
+            .iter()
+            .filter(|&&byte| byte == b'\n')
+            .count() as u64;
+        self.position = position;
+        self.line
+    }
+}
+
+fn name(name: &[u8]) -> String {
+    String::from_utf8_lossy(name).into_owned()
+}
+
+fn attributes(element: &BytesStart) -> Result<Vec<(String, String)>, String> {
+    element
+        .attributes()
+        .map(|attribute| {
+            let attribute = attribute.map_err(|e| e.to_string())?;
+            let value = attribute.unescape_value().map_err(|e| e.to_string())?;
+            Ok((name(attribute.key.as_ref()), value.into_owned()))
+        })
+        .collect()
+}
+
+fn attribute<'a>(attributes: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value.as_str())
+}
+
+struct Edge {
+    source: String,
+    target: String,
+    directed: Option<bool>,
+    weight: Option<String>,
+    line: u64,
+}
+
+impl GraphReader for GraphmlReader {
+    fn read(&self, path: &str, edges: &mut EdgeList) -> Result<(), Error> {
+        let text = stream::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        let mut reader = Reader::from_str(&text);
+        let mut lines = Lines {
+            text: &text,
+            position: 0,
+            line: 1,
+        };
+        let mut open: Vec<String> = Vec::new();
+        let mut weight_key: Option<String> = None;
+        let mut default_weight: Option<String> = None;
+        let mut in_weight_key = false;
+        let mut directed = false;
+        let mut edge: Option<Edge> = None;
+        // text of the element currently holding a weight
+        let mut weight: Option<String> = None;
+
+        loop {
+            let line = lines.at(reader.buffer_position());
+            let event = reader.read_event().map_err(|e| {
+                let line = lines.at(reader.error_position());
+                Error::parse(path, line, e.to_string())
+            })?;
+            let (element, empty) = match event {
+                Event::Start(element) => (element, false),
+                Event::Empty(element) => (element, true),
+                Event::Text(text) => {
+                    if let Some(weight) = weight.as_mut() {
+                        let text = text
+                            .unescape()
+                            .map_err(|e| Error::parse(path, line, e.to_string()))?;
+                        weight.push_str(&text);
+                    }
+                    continue;
+                }
+                Event::CData(text) => {
+                    if let Some(weight) = weight.as_mut() {
+                        let text = text
+                            .decode()
+                            .map_err(|e| Error::parse(path, line, e.to_string()))?;
+                        weight.push_str(&text);
+                    }
+                    continue;
+                }
+                Event::End(element) => {
+                    // the reader checks that end tags match their start
+                    open.pop();
+                    match name(element.local_name().as_ref()).as_str() {
+                        "default" if weight.is_some() => default_weight = weight.take(),
+                        "data" if weight.is_some() => {
+                            if let Some(edge) = edge.as_mut() {
+                                edge.weight = weight.take();
+                            }
+                        }
+                        "key" => in_weight_key = false,
+                        "edge" => {
+                            if let Some(edge) = edge.take() {
+                                push(edges, edge, directed, &default_weight)?;
+                            }
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                Event::Eof => break,
+                // declarations, processing instructions and comments
+                _ => continue,
+            };
+            let name = self::name(element.local_name().as_ref());
+            let attributes =
+                attributes(&element).map_err(|message| Error::parse(path, line, message))?;
+            let required = |key| {
+                attribute(&attributes, key)
+                    .map(str::to_string)
+                    .ok_or_else(|| {
+                        Error::parse(
+                            path,
+                            line,
+                            format!("<{}> without a {} attribute", name, key),
+                        )
+                    })
+            };
+            let parent = open.last().map(String::as_str);
+            match name.as_str() {
+                "key" => {
+                    let domain = attribute(&attributes, "for").unwrap_or("all");
+                    in_weight_key = attribute(&attributes, "attr.name") == Some("weight")
+                        && (domain == "edge" || domain == "all");
+                    if in_weight_key {
+                        weight_key = Some(required("id")?);
+                    }
+                }
+                "default" if in_weight_key && parent == Some("key") && !empty => {
+                    weight = Some(String::new());
+                }
+                "graph" => directed = attribute(&attributes, "edgedefault") == Some("directed"),
+                "node" => edges.declare(line, required("id")?)?,
+                "edge" => {
+                    let edge_directed = match attribute(&attributes, "directed") {
+                        Some("true") => Some(true),
+                        Some("false") => Some(false),
+                        _ => None,
+                    };
+                    let new_edge = Edge {
+                        source: required("source")?,
+                        target: required("target")?,
+                        directed: edge_directed,
+                        weight: None,
+                        line,
+                    };
+                    match empty {
+                        true => push(edges, new_edge, directed, &default_weight)?,
+                        false => edge = Some(new_edge),
+                    }
+                }
+                "data"
+                    if parent == Some("edge")
+                        && !empty
+                        && weight_key.is_some()
+                        && attribute(&attributes, "key") == weight_key.as_deref() =>
+                {
+                    weight = Some(String::new());
+                }
+                "hyperedge" => {
+                    return Err(Error::parse(
+                        path,
+                        line,
+                        "hyperedges are not supported".to_string(),
+                    ))
+                }
+                _ => {}
+            }
+            if !empty {
+                open.push(name);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn push(
+    edges: &mut EdgeList,
+    edge: Edge,
+    directed: bool,
+    default_weight: &Option<String>,
+) -> Result<(), Error> {
+    let weight = match edge.weight.as_ref().or(default_weight.as_ref()) {
+        Some(weight) => edges.weight(edge.line, None, weight)?,
+        None => None,
+    };
+    match edge.directed.unwrap_or(directed) {
+        true => edges.push(edge.line, edge.source, edge.target, weight),
+        false => edges.push_undirected(edge.line, edge.source, edge.target, weight),
+    }
+}
@@ -0,0 +1,187 @@
+use crate::error::Error;
+use crate::input::{EdgeList, GraphReader};
+use crate::stream;
+
+/// Reads a Matrix Market file as an adjacency matrix, labelling nodes with their 1-based row
+/// and column numbers. Every stored entry of a `coordinate` matrix is an edge, pattern entries
+/// with the default weight, while `array` matrices only have edges for their non-zero entries.
+pub(crate) struct MatrixMarketReader;
+
+#[derive(PartialEq)]
+enum Layout {
+    Coordinate,
+    Array,
+}
+
+impl GraphReader for MatrixMarketReader {
+    fn read(&self, path: &str, edges: &mut EdgeList) -> Result<(), Error> {
+        let text = stream::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        let mut lines = text.lines().zip(1..);
+
+        let banner: Vec<String> = match lines.next() {
+            Some((line, _)) => line
+                .split_whitespace()
+                .map(str::to_ascii_lowercase)
+                .collect(),
+            None => Vec::new(),
+        };
+        let (layout, field, symmetric) = match &banner[..] {
+            [banner, object, layout, field, symmetry]
+                if banner == "%%matrixmarket" && object == "matrix" =>
+            {
+                let layout = match layout.as_str() {
+                    "coordinate" => Layout::Coordinate,
+                    "array" => Layout::Array,
+                    _ => return Err(Error::parse(path, 1, format!("unknown format {}", layout))),
+                };
+                let field = match field.as_str() {
+                    "real" | "integer" | "pattern" => field.as_str(),
+                    _ => {
+                        return Err(Error::parse(
+                            path,
+                            1,
+                            format!("unsupported field {}", field),
+                        ))
+                    }
+                };
+                let symmetric = match symmetry.as_str() {
+                    "general" => false,
+                    "symmetric" => true,
+                    _ => {
+                        return Err(Error::parse(
+                            path,
+                            1,
+                            format!("unsupported symmetry {}", symmetry),
+                        ))
+                    }
+                };
+                (layout, field, symmetric)
+            }
+            _ => {
+                return Err(Error::parse(
+                    path,
+                    1,
+                    "expected a %%MatrixMarket matrix header".to_string(),
+                ))
+            }
+        };
+        if layout == Layout::Array && field == "pattern" {
+            return Err(Error::parse(
+                path,
+                1,
+                "array matrices can't be patterns".to_string(),
+            ));
+        }
+
+        let mut entries = lines.filter(|(line, _)| {
+            let line = line.trim_start();
+            !line.is_empty() && !line.starts_with('%')
+        });
+        let (size, size_line) = entries
+            .next()
+            .ok_or_else(|| Error::parse(path, 1, "missing the matrix size".to_string()))?;
+        let size = size
+            .split_whitespace()
+            .map(|number| number.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| Error::parse(path, size_line, format!("invalid matrix size: {}", e)))?;
+        let (rows, columns, stored) = match (&layout, &size[..]) {
+            (Layout::Coordinate, &[rows, columns, stored]) => (rows, columns, stored),
+            (Layout::Array, &[rows, columns]) => (rows, columns, rows * columns),
+            _ => {
+                return Err(Error::parse(
+                    path,
+                    size_line,
+                    format!("unexpected matrix size {:?}", size),
+                ))
+            }
+        };
+        if symmetric && rows != columns {
+            return Err(Error::parse(
+                path,
+                size_line,
+                "symmetric matrices must be square".to_string(),
+            ));
+        }
+        for node in 1..=rows.max(columns) {
+            edges.declare(size_line, node.to_string())?;
+        }
+
+        let push = |edges: &mut EdgeList, line, row: usize, column: usize, weight| {
+            let (src, dst) = (row.to_string(), column.to_string());
+            match symmetric {
+                true => edges.push_undirected(line, src, dst, weight),
+                false => edges.push(line, src, dst, weight),
+            }
+        };
+        let mut found = 0;
+        match layout {
+            Layout::Coordinate => {
+                for (entry, line) in entries {
+                    let fields: Vec<&str> = entry.split_whitespace().collect();
+                    let index = |field: Option<&&str>, len: usize| {
+                        field
+                            .and_then(|field| field.parse::<usize>().ok())
+                            .filter(|&index| index >= 1 && index <= len)
+                            .ok_or_else(|| {
+                                Error::parse(path, line, format!("invalid entry {:?}", entry))
+                            })
+                    };
+                    let row = index(fields.first(), rows)?;
+                    let column = index(fields.get(1), columns)?;
+                    let weight = match (field, fields.get(2)) {
+                        ("pattern", _) => None,
+                        (_, Some(weight)) => edges.weight(line, None, weight)?,
+                        (_, None) => {
+                            return Err(Error::parse(
+                                path,
+                                line,
+                                format!("missing value in {:?}", entry),
+                            ))
+                        }
+                    };
+                    push(edges, line, row, column, weight)?;
+                    found += 1;
+                }
+            }
+            Layout::Array => {
+                // values are listed column by column, only the lower triangle for symmetric
+                // matrices
+                let mut values = entries.flat_map(|(entry, line)| {
+                    entry.split_whitespace().map(move |value| (value, line))
+                });
+                for column in 1..=columns {
+                    let first_row = if symmetric { column } else { 1 };
+                    for row in first_row..=rows {
+                        let (value, line) = match values.next() {
+                            Some(value) => value,
+                            None => break,
+                        };
+                        found += 1;
+                        let weight = value.parse::<f32>().map_err(|_| {
+                            Error::parse(path, line, format!("invalid value {:?}", value))
+                        })?;
+                        if weight != 0.0 {
+                            let weight = edges.weight(line, None, value)?;
+                            push(edges, line, row, column, weight)?;
+                        }
+                    }
+                }
+                found += values.count();
+            }
+        }
+        let expected = match (&layout, symmetric) {
+            (Layout::Array, true) => rows * (rows + 1) / 2,
+            _ => stored,
+        };
+        if found != expected {
+            return Err(Error::Parse {
+                path: path.to_string(),
+                line: None,
+                column: None,
+                message: format!("expected {} entries, found {}", expected, found),
+            });
+        }
+        Ok(())
+    }
+}
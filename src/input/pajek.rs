@@ -0,0 +1,160 @@
+use crate::error::Error;
+use crate::input::{EdgeList, GraphReader};
+use crate::stream;
+
+/// Reads a Pajek `.net` file. Vertices are labelled by their quoted label, or by their number
+/// when they don't have one, and `*Edges` are undirected whatever the `*Arcs` are.
+pub(crate) struct PajekReader;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Preamble,
+    Vertices,
+    Arcs,
+    Edges,
+    ArcsList,
+    EdgesList,
+    Matrix,
+}
+
+// Splits a line on whitespace, keeping double-quoted labels together without their quotes.
+fn tokens(line: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut rest = line.trim_start();
+    while !rest.is_empty() {
+        let (token, tail) = match rest.strip_prefix('"') {
+            Some(quoted) => match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            },
+            None => {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                rest.split_at(end)
+            }
+        };
+        tokens.push(token);
+        rest = tail.trim_start();
+    }
+    tokens
+}
+
+impl GraphReader for PajekReader {
+    fn read(&self, path: &str, edges: &mut EdgeList) -> Result<(), Error> {
+        let text = stream::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        let mut section = Section::Preamble;
+        let mut labels: Vec<String> = Vec::new();
+        let mut vertices_line = 0;
+        let mut matrix_row = 0;
+
+        for (line, number) in text.lines().zip(1..) {
+            let tokens = tokens(line);
+            let first = match tokens.first() {
+                Some(first) if !first.starts_with('%') => *first,
+                _ => continue,
+            };
+            if first.starts_with('*') {
+                section = match first.to_ascii_lowercase().as_str() {
+                    "*network" => Section::Preamble,
+                    "*vertices" => {
+                        let count = tokens.get(1).and_then(|count| count.parse().ok());
+                        let count: usize = count.ok_or_else(|| {
+                            Error::parse(
+                                path,
+                                number,
+                                format!("invalid vertex count in {:?}", line),
+                            )
+                        })?;
+                        labels = (1..=count).map(|vertex| vertex.to_string()).collect();
+                        vertices_line = number;
+                        Section::Vertices
+                    }
+                    "*arcs" => Section::Arcs,
+                    "*edges" => Section::Edges,
+                    "*arcslist" => Section::ArcsList,
+                    "*edgeslist" => Section::EdgesList,
+                    "*matrix" => {
+                        matrix_row = 0;
+                        Section::Matrix
+                    }
+                    _ => {
+                        return Err(Error::parse(
+                            path,
+                            number,
+                            format!("unsupported section {}", first),
+                        ))
+                    }
+                };
+                continue;
+            }
+
+            let index = |token: &str| {
+                token
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&vertex| vertex >= 1 && vertex <= labels.len())
+                    .map(|vertex| vertex - 1)
+                    .ok_or_else(|| Error::parse(path, number, format!("unknown vertex {}", token)))
+            };
+            let vertex = |token: &str| Ok::<_, Error>(labels[index(token)?].clone());
+            match section {
+                Section::Preamble => {
+                    return Err(Error::parse(
+                        path,
+                        number,
+                        "expected a *Vertices section".to_string(),
+                    ))
+                }
+                Section::Vertices => {
+                    let index = index(first)?;
+                    if let Some(label) = tokens.get(1) {
+                        labels[index] = label.to_string();
+                    }
+                }
+                Section::Arcs | Section::Edges => {
+                    let target = tokens.get(1).ok_or_else(|| {
+                        Error::parse(path, number, format!("missing target in {:?}", line))
+                    })?;
+                    let (src, dst) = (vertex(first)?, vertex(target)?);
+                    let weight = match tokens.get(2) {
+                        Some(weight) => edges.weight(number, None, weight)?,
+                        None => None,
+                    };
+                    match section {
+                        Section::Edges => edges.push_undirected(number, src, dst, weight)?,
+                        _ => edges.push(number, src, dst, weight)?,
+                    }
+                }
+                Section::ArcsList | Section::EdgesList => {
+                    let src = vertex(first)?;
+                    for target in &tokens[1..] {
+                        let dst = vertex(target)?;
+                        match section {
+                            Section::EdgesList => {
+                                edges.push_undirected(number, src.clone(), dst, None)?
+                            }
+                            _ => edges.push(number, src.clone(), dst, None)?,
+                        }
+                    }
+                }
+                Section::Matrix => {
+                    matrix_row += 1;
+                    let src = vertex(&matrix_row.to_string())?;
+                    for (column, value) in tokens.iter().enumerate() {
+                        let weight = value.parse::<f32>().map_err(|_| {
+                            Error::parse(path, number, format!("invalid value {:?}", value))
+                        })?;
+                        if weight != 0.0 {
+                            let dst = vertex(&(column + 1).to_string())?;
+                            let weight = edges.weight(number, None, value)?;
+                            edges.push(number, src.clone(), dst, weight)?;
+                        }
+                    }
+                }
+            }
+        }
+        for label in labels {
+            edges.declare(vertices_line, label)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use crate::csr::Csr;
+use crate::dijkstra::DijkstraSearch;
+use crate::error::Error;
+use crate::labels::IndexedEdge;
+use crate::parallel::for_each_source;
+use crate::{AllPairs, ShortestPathLength};
+
+/// Graph with possibly negative weights, searched with Johnson's algorithm: Bellman-Ford
+/// potentials make every edge non-negative, so each source can then be searched with
+/// Dijkstra's algorithm and its lengths shifted back.
+pub struct JohnsonGraph {
+    // targets with their reweighted, non-negative lengths
+    csr: Csr<(usize, u64)>,
+    potentials: Vec<i64>,
+}
+
+// Bellman-Ford from a virtual source joined to every node by a zero length edge. Returns the
+// distances from it, or the nodes of a negative cycle in path order.
+fn potentials(num_nodes: usize, arcs: &[(usize, usize, i64)]) -> Result<Vec<i64>, Vec<usize>> {
+    let mut distances = vec![0; num_nodes];
+    let mut predecessors = vec![None; num_nodes];
+    // the zero lengths from the virtual source count as the first of the num_nodes rounds
+    // needed, so a relaxation in the last round means a negative cycle
+    let mut relaxed = None;
+    for _ in 0..num_nodes {
+        relaxed = None;
+        for &(src, dst, weight) in arcs {
+            if distances[src] + weight < distances[dst] {
+                distances[dst] = distances[src] + weight;
+                predecessors[dst] = Some(src);
+                relaxed = Some(dst);
+            }
+        }
+        if relaxed.is_none() {
+            break;
+        }
+    }
+    let mut node = match relaxed {
+        Some(node) => node,
+        None => return Ok(distances),
+    };
+    // walking back num_nodes predecessors is sure to end up on the cycle
+    for _ in 0..num_nodes {
+        node = predecessors[node].unwrap();
+    }
+    let mut cycle = vec![node];
+    let mut previous = predecessors[node].unwrap();
+    while previous != node {
+        cycle.push(previous);
+        previous = predecessors[previous].unwrap();
+    }
+    cycle.reverse();
+    Err(cycle)
+}
+
+pub(crate) fn into_johnson_graph(
+    num_nodes: usize,
+    edges: &[IndexedEdge],
+    weight_scale: f64,
+    directed: bool,
+) -> Result<JohnsonGraph, Vec<usize>> {
+    let mut arcs = Vec::with_capacity(edges.len() * 2);
+    for edge in edges {
+        let weight = (edge.weight as f64 * weight_scale).round() as i64;
+        arcs.push((edge.src, edge.dst, weight));
+        if !directed {
+            arcs.push((edge.dst, edge.src, weight));
+        }
+    }
+    let potentials = potentials(num_nodes, &arcs)?;
+
+    let arcs: Vec<_> = arcs
+        .into_iter()
+        .map(|(src, dst, weight)| {
+            let weight = weight + potentials[src] - potentials[dst];
+            (src, (dst, weight as u64))
+        })
+        .collect();
+    Ok(JohnsonGraph {
+        csr: Csr::new(num_nodes, &arcs),
+        potentials,
+    })
+}
+
+impl AllPairs for JohnsonGraph {
+    fn num_nodes(&self) -> usize {
+        self.csr.num_nodes()
+    }
+
+    fn shortest_paths_from(
+        &self,
+        sources: &[usize],
+        threads: usize,
+        sink: &mut dyn FnMut(&[ShortestPathLength]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        for_each_source(
+            sources,
+            threads,
+            || DijkstraSearch::new(self.num_nodes()),
+            |search, src, res| {
+                for (dst, distance) in search.distances_from(&self.csr, src, None) {
+                    res.push(ShortestPathLength {
+                        src,
+                        dst,
+                        length: distance as i64 - self.potentials[src] + self.potentials[dst],
+                    })
+                }
+            },
+            sink,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AllPairs, Graph};
+
+    fn graph(edges: &[(&str, &str, f32)]) -> Graph {
+        let mut builder = Graph::builder().directed(true).negative_weights(true);
+        for &(src, dst, weight) in edges {
+            builder.add_edge(src, dst, weight).unwrap();
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn negative_cycle() {
+        let graph = graph(&[("a", "b", 1.0), ("b", "c", -3.0), ("c", "a", 1.0)]);
+        let error = graph.johnson().err().unwrap();
+        assert_eq!(error.to_string(), "negative cycle b -> c -> a -> b");
+    }
+
+    #[test]
+    fn negative_weights() {
+        let graph = graph(&[("a", "b", 2.0), ("b", "c", -2.0), ("a", "c", 1.0)]);
+        let mut lengths = Vec::new();
+        let johnson = graph.johnson().unwrap();
+        johnson
+            .shortest_paths(1, &mut |paths| {
+                lengths.extend(paths.iter().map(|path| (path.src, path.dst, path.length)));
+                Ok(())
+            })
+            .unwrap();
+        lengths.sort();
+        assert_eq!(
+            lengths,
+            vec![
+                (0, 0, 0),
+                (0, 1, 2),
+                (0, 2, 0),
+                (1, 1, 0),
+                (1, 2, -2),
+                (2, 2, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn negative_weights_need_johnson() {
+        let graph = graph(&[("a", "b", -1.0)]);
+        assert!(graph.dijkstra().is_err());
+        assert!(graph.floyd_warshall().is_err());
+        assert!(graph.fast_path().is_err());
+        assert!(graph.johnson().is_ok());
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+/// Maps arbitrary node labels to the dense indices used by the shortest path backends.
+#[derive(Default, Debug)]
+pub struct NodeLabels {
+    labels: Vec<String>,
+    indices: HashMap<String, usize>,
+}
+
+impl NodeLabels {
+    pub(crate) fn intern(&mut self, label: String) -> usize {
+        if let Some(&index) = self.indices.get(&label) {
+            return index;
+        }
+        let index = self.labels.len();
+        self.labels.push(label.clone());
+        self.indices.insert(label, index);
+        index
+    }
+
+    /// The index of `label`, if the graph has such a node.
+    pub fn index(&self, label: &str) -> Option<usize> {
+        self.indices.get(label).copied()
+    }
+
+    pub fn label(&self, index: usize) -> &str {
+        &self.labels[index]
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct IndexedEdge {
+    pub(crate) src: usize,
+    pub(crate) dst: usize,
+    pub(crate) weight: f32,
+}
+
+// Integral floats such as `3.0` are treated as the integer label `3`, so ids exported by tools
+// that write every number as a float still line up with plain integer ids. Returns the label
+// back as an error when it is too large to be such an id.
+pub(crate) fn normalize_label(label: String) -> Result<String, String> {
+    match label.parse::<f64>() {
+        Ok(value) if label.contains('.') && value.fract() == 0.0 && value >= 0.0 => {
+            if value >= u64::MAX as f64 {
+                return Err(label);
+            }
+            Ok((value as u64).to_string())
+        }
+        _ => Ok(label),
+    }
+}
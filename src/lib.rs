@@ -0,0 +1,835 @@
+//! All-pairs shortest path lengths and network portraits of graphs read from edge lists,
+//! GraphML, GML, Pajek or Matrix Market files.
+//!
+//! Build a [`Graph`] with [`Graph::builder`], pick a backend with [`Graph::prepare`] and stream
+//! its shortest paths through [`AllPairs::shortest_paths`], or summarize them as a
+//! [`Portrait`].
+
+use std::collections::HashMap;
+use std::fmt;
+
+use fast_paths::{FastGraph, InputGraph};
+use serde::Deserialize;
+
+use crate::bfs::into_bfs_graph;
+use crate::dijkstra::into_dijkstra_graph;
+use crate::floyd_warshall::into_floyd_warshall_graph;
+use crate::input::{read_edge_list, read_node_labels};
+use crate::johnson::into_johnson_graph;
+use crate::labels::{normalize_label, IndexedEdge};
+
+pub use crate::bfs::BfsGraph;
+pub use crate::dijkstra::DijkstraGraph;
+pub use crate::error::Error;
+pub use crate::floyd_warshall::FloydWarshallGraph;
+pub use crate::input::{Column, Delimiter, EdgeListOptions, InputFormat};
+pub use crate::johnson::JohnsonGraph;
+pub use crate::labels::NodeLabels;
+pub use crate::phast::PhastGraph;
+pub use crate::portrait::{
+    bin_edges, portrait_divergence, shared_bin_edges, Binning, ObservedLengths, Portrait,
+};
+pub use crate::prepared::is_prepared;
+pub use crate::subset::{sample_sources, Subset, Targets};
+
+// Declares a C-like enum parsed from one name per variant, so `NAMES` and its `FromStr` impl
+// come from the same list. `$kind` describes the enum in the error for an unknown name.
+macro_rules! named_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident($kind:literal) {
+            $($(#[$variant_meta:meta])* $variant:ident = $text:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+        }
+
+        impl $name {
+            #[doc = concat!("The names [`", stringify!($name), "`] is parsed from, one per variant.")]
+            pub const NAMES: &'static [&'static str] = &[$($text),*];
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($text => Ok($name::$variant),)*
+                    _ => Err($crate::unknown_name($kind, s, $name::NAMES)),
+                }
+            }
+        }
+    };
+}
+
+mod bfs;
+mod csr;
+mod dijkstra;
+mod error;
+mod floyd_warshall;
+mod input;
+mod johnson;
+mod labels;
+mod npy;
+pub mod output;
+mod parallel;
+mod phast;
+mod portrait;
+mod prepared;
+mod stream;
+mod subset;
+
+const MAX_WEIGHT_VALUE: f32 = 4294967296_f32;
+
+named_enum! {
+    #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+    pub enum Algorithm("algorithm") {
+        Dijkstra = "dijkstra",
+        FastPath = "fast-path",
+        Bfs = "bfs",
+        /// Supports negative weights, but not negative cycles
+        Johnson = "johnson",
+        /// Computes a dense matrix up front, for small and dense graphs
+        FloydWarshall = "floyd-warshall",
+        /// Picks one of the others to suit the graph
+        Auto = "auto",
+    }
+}
+
+// Floyd-Warshall only pays off below this many nodes, as its matrix grows quadratically.
+const MAX_FLOYD_WARSHALL_NODES: usize = 2000;
+
+// Floyd-Warshall's matrix takes 8 GiB at this many nodes, beyond which it is refused outright.
+const MAX_FLOYD_WARSHALL_MATRIX_NODES: usize = 32768;
+
+named_enum! {
+    /// How to merge edges that join the same nodes more than once. Undirected edges are the same
+    /// whichever way round they are given.
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub enum DuplicateEdges("duplicate edge policy") {
+        Min = "min",
+        Max = "max",
+        Sum = "sum",
+        /// Keeps the weight of the edge seen first
+        First = "first",
+        Error = "error",
+    }
+}
+
+named_enum! {
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub enum SelfLoops("self-loop policy") {
+        /// Drops the edge but keeps its node, as a self-loop never shortens a path
+        Drop = "drop",
+        Error = "error",
+    }
+}
+
+// The error for a name that isn't one of `names`.
+pub(crate) fn unknown_name(kind: &str, name: &str, names: &[&str]) -> String {
+    format!(
+        "unknown {} {:?}, expected one of {}",
+        kind,
+        name,
+        names.join(", ")
+    )
+}
+
+#[derive(Clone, Debug)]
+struct WeightedNodes {
+    src: String,
+    dst: String,
+    weight: f32,
+    line: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct NodeLabel {
+    label: String,
+}
+
+/// The shortest path from `src` to `dst`, whose `length` is in units of the graph's weight
+/// scale. Only negative when the graph has negative weights.
+#[derive(Clone, Debug)]
+pub struct ShortestPathLength {
+    pub src: usize,
+    pub dst: usize,
+    pub length: i64,
+}
+
+/// A shortest path backend, answering for every source in turn.
+pub trait AllPairs: Sync {
+    /// Counts every node of the graph, including isolated ones.
+    fn num_nodes(&self) -> usize;
+
+    /// Counts the sources [`AllPairs::shortest_paths`] searches from, which is every node
+    /// unless restricted by a [`Subset`].
+    fn num_sources(&self) -> usize {
+        self.num_nodes()
+    }
+
+    /// The sources [`AllPairs::shortest_paths`] searches from, in the order their paths come.
+    fn sources(&self) -> Vec<usize> {
+        (0..self.num_nodes()).collect()
+    }
+
+    /// The nodes [`AllPairs::shortest_paths`] reports paths to, in index order, which is every
+    /// node unless restricted by a [`Subset`].
+    fn targets(&self) -> Vec<usize> {
+        (0..self.num_nodes()).collect()
+    }
+
+    /// Streams the shortest paths of each of `sources`, in the order given, into `sink`.
+    /// Sources are shared by `threads` workers, and the first error returned by `sink` stops
+    /// the search.
+    fn shortest_paths_from(
+        &self,
+        sources: &[usize],
+        threads: usize,
+        sink: &mut dyn FnMut(&[ShortestPathLength]) -> Result<(), Error>,
+    ) -> Result<(), Error>;
+
+    /// Like [`AllPairs::shortest_paths_from`], but only passes on the paths to `targets`.
+    /// Backends that search outward from each source override this to stop once every target
+    /// is settled.
+    fn shortest_paths_to(
+        &self,
+        sources: &[usize],
+        targets: &Targets,
+        threads: usize,
+        sink: &mut dyn FnMut(&[ShortestPathLength]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let mut paths = Vec::new();
+        self.shortest_paths_from(sources, threads, &mut |source_paths| {
+            paths.clear();
+            paths.extend(
+                source_paths
+                    .iter()
+                    .filter(|path| targets.contains(path.dst))
+                    .cloned(),
+            );
+            sink(&paths)
+        })
+    }
+
+    /// Streams the shortest paths of every source, in source order, into `sink`.
+    fn shortest_paths(
+        &self,
+        threads: usize,
+        sink: &mut dyn FnMut(&[ShortestPathLength]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let sources: Vec<usize> = (0..self.num_nodes()).collect();
+        self.shortest_paths_from(&sources, threads, sink)
+    }
+}
+
+// Weights are checked as they are read, so building the graphs afterwards can't fail.
+fn check_weight(weight: f32, weight_scale: f64) -> Result<(), String> {
+    if !(weight_scale.is_finite() && weight_scale > 0.0) {
+        return Err(format!(
+            "the weight scale {} must be finite and positive",
+            weight_scale
+        ));
+    }
+    if !weight.is_finite() {
+        return Err("weights must be finite".to_string());
+    }
+    let scaled = (weight as f64 * weight_scale).round();
+    // fast paths would drop the edge while the other backends keep it at length 0
+    if scaled == 0.0 && weight != 0.0 {
+        return Err(format!(
+            "scaled by {} it rounds to 0, raise the weight scale",
+            weight_scale
+        ));
+    }
+    if scaled.abs() > MAX_WEIGHT_VALUE as f64 {
+        return Err(format!(
+            "scaled by {} it exceeds the maximum weight {}",
+            weight_scale, MAX_WEIGHT_VALUE
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn scale_weight(weight: f32, weight_scale: f64) -> usize {
+    (weight as f64 * weight_scale).round() as usize
+}
+
+fn into_input_graph(edges: &[IndexedEdge], weight_scale: f64, directed: bool) -> InputGraph {
+    let mut input_graph = InputGraph::new();
+    for edge in edges.iter() {
+        let weight = scale_weight(edge.weight, weight_scale);
+        if directed {
+            input_graph.add_edge(edge.src, edge.dst, weight);
+        } else {
+            input_graph.add_edge_bidir(edge.src, edge.dst, weight);
+        }
+    }
+    input_graph.freeze();
+    input_graph
+}
+
+/// Counts of the edges added to a [`Graph`], and of those merged or dropped on the way.
+#[derive(Clone, Debug, Default)]
+pub struct EdgeSummary {
+    pub edges: usize,
+    pub duplicate_edges: usize,
+    pub self_loops: usize,
+}
+
+impl fmt::Display for EdgeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} edges, {} duplicate edges merged, {} self-loops dropped",
+            self.edges, self.duplicate_edges, self.self_loops
+        )
+    }
+}
+
+/// Collects the nodes and edges of a [`Graph`]. Set `directed`, `weight_scale` and the edge
+/// policies before adding edges, as edges are checked and merged as they come in.
+pub struct GraphBuilder {
+    labels: NodeLabels,
+    edges: Vec<IndexedEdge>,
+    directed: bool,
+    weight_scale: f64,
+    duplicate_edges: DuplicateEdges,
+    self_loops: SelfLoops,
+    negative_weights: bool,
+    // position in `edges` of the edge between two nodes, smallest first unless directed
+    edge_index: HashMap<(usize, usize), usize>,
+    summary: EdgeSummary,
+}
+
+impl Default for GraphBuilder {
+    fn default() -> Self {
+        GraphBuilder {
+            labels: NodeLabels::default(),
+            edges: Vec::new(),
+            directed: false,
+            weight_scale: 1.0,
+            duplicate_edges: DuplicateEdges::Min,
+            self_loops: SelfLoops::Drop,
+            negative_weights: false,
+            edge_index: HashMap::new(),
+            summary: EdgeSummary::default(),
+        }
+    }
+}
+
+impl GraphBuilder {
+    /// Makes edges one-way from their source to their target.
+    pub fn directed(mut self, directed: bool) -> Self {
+        self.directed = directed;
+        self
+    }
+
+    /// Fixed-point scale applied to edge weights before they are rounded to integers.
+    pub fn weight_scale(mut self, weight_scale: f64) -> Self {
+        self.weight_scale = weight_scale;
+        self
+    }
+
+    pub fn duplicate_edges(mut self, duplicate_edges: DuplicateEdges) -> Self {
+        self.duplicate_edges = duplicate_edges;
+        self
+    }
+
+    pub fn self_loops(mut self, self_loops: SelfLoops) -> Self {
+        self.self_loops = self_loops;
+        self
+    }
+
+    /// Accepts negative weights, which only [`Algorithm::Johnson`] can search, and which make
+    /// [`Algorithm::Auto`] pick it.
+    pub fn negative_weights(mut self, negative_weights: bool) -> Self {
+        self.negative_weights = negative_weights;
+        self
+    }
+
+    /// Adds an edge. Labels are normalized as in graph files, so `3.0` is the node `3`.
+    pub fn add_edge(&mut self, src: &str, dst: &str, weight: f32) -> Result<(), Error> {
+        check_weight(weight, self.weight_scale)
+            .map_err(|reason| Error::Invalid(format!("invalid weight {}: {}", weight, reason)))?;
+        let edge = IndexedEdge {
+            src: self.intern(src)?,
+            dst: self.intern(dst)?,
+            weight,
+        };
+        self.insert(edge).map_err(Error::Invalid)
+    }
+
+    fn intern(&mut self, label: &str) -> Result<usize, Error> {
+        let label = normalize_label(label.to_string()).map_err(|label| {
+            Error::Invalid(format!(
+                "node id {} does not fit in a 64-bit integer",
+                label
+            ))
+        })?;
+        Ok(self.labels.intern(label))
+    }
+
+    // Applies the self-loop and duplicate edge policies, with an error message when they
+    // reject the edge.
+    fn insert(&mut self, edge: IndexedEdge) -> Result<(), String> {
+        if edge.weight < 0.0 && !self.negative_weights {
+            return Err(format!(
+                "invalid weight {}: negative weights need the johnson algorithm",
+                edge.weight
+            ));
+        }
+        self.summary.edges += 1;
+        if edge.src == edge.dst {
+            let label = self.labels.label(edge.src);
+            self.summary.self_loops += 1;
+            return match self.self_loops {
+                SelfLoops::Drop if edge.weight < 0.0 => {
+                    Err(format!("negative cycle {} -> {}", label, label))
+                }
+                SelfLoops::Drop => Ok(()),
+                SelfLoops::Error => Err(format!("self-loop on {}", label)),
+            };
+        }
+        let key = match self.directed || edge.src < edge.dst {
+            true => (edge.src, edge.dst),
+            false => (edge.dst, edge.src),
+        };
+        let index = match self.edge_index.get(&key) {
+            Some(&index) => index,
+            None => {
+                self.edge_index.insert(key, self.edges.len());
+                self.edges.push(edge);
+                return Ok(());
+            }
+        };
+        self.summary.duplicate_edges += 1;
+        let name = || {
+            format!(
+                "{} {} {}",
+                self.labels.label(edge.src),
+                if self.directed { "->" } else { "--" },
+                self.labels.label(edge.dst)
+            )
+        };
+        let weight = self.edges[index].weight;
+        let weight = match self.duplicate_edges {
+            DuplicateEdges::Min => weight.min(edge.weight),
+            DuplicateEdges::Max => weight.max(edge.weight),
+            DuplicateEdges::Sum => {
+                let sum = weight + edge.weight;
+                check_weight(sum, self.weight_scale).map_err(|reason| {
+                    format!("summed weight {} of edge {}: {}", sum, name(), reason)
+                })?;
+                sum
+            }
+            DuplicateEdges::First => weight,
+            DuplicateEdges::Error => return Err(format!("duplicate edge {}", name())),
+        };
+        self.edges[index].weight = weight;
+        Ok(())
+    }
+
+    /// Adds a node, so it is part of the graph even without edges.
+    pub fn add_node(&mut self, label: &str) -> Result<(), Error> {
+        self.intern(label).map(drop)
+    }
+
+    /// Adds the nodes and edges of the graph file at `path`.
+    pub fn read_edges(&mut self, path: &str, options: &EdgeListOptions) -> Result<(), Error> {
+        let edge_list = read_edge_list(path, options, self.directed, self.weight_scale)?;
+        for edge in edge_list.edges {
+            let line = edge.line;
+            let edge = IndexedEdge {
+                src: self.labels.intern(edge.src),
+                dst: self.labels.intern(edge.dst),
+                weight: edge.weight,
+            };
+            self.insert(edge)
+                .map_err(|message| Error::parse(path, line, message))?;
+        }
+        for node in edge_list.nodes {
+            self.labels.intern(node);
+        }
+        Ok(())
+    }
+
+    /// Adds the nodes listed one per line in the file at `path`.
+    pub fn read_nodes(&mut self, path: &str) -> Result<(), Error> {
+        for node in read_node_labels(path)? {
+            self.labels.intern(node.label);
+        }
+        Ok(())
+    }
+
+    pub fn build(self) -> Graph {
+        Graph {
+            labels: self.labels,
+            edges: self.edges,
+            directed: self.directed,
+            weight_scale: self.weight_scale,
+            summary: self.summary,
+            fast_graph: None,
+        }
+    }
+}
+
+/// A graph whose nodes are numbered in the order they were first seen.
+pub struct Graph {
+    labels: NodeLabels,
+    edges: Vec<IndexedEdge>,
+    directed: bool,
+    weight_scale: f64,
+    summary: EdgeSummary,
+    // contraction hierarchy of a prepared graph
+    fast_graph: Option<FastGraph>,
+}
+
+impl Graph {
+    pub fn builder() -> GraphBuilder {
+        GraphBuilder::default()
+    }
+
+    pub fn labels(&self) -> &NodeLabels {
+        &self.labels
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn weight_scale(&self) -> f64 {
+        self.weight_scale
+    }
+
+    pub fn summary(&self) -> &EdgeSummary {
+        &self.summary
+    }
+
+    /// The indices of the nodes listed one per line in the file at `path`, in that order.
+    pub fn read_node_indices(&self, path: &str) -> Result<Vec<usize>, Error> {
+        read_node_labels(path)?
+            .into_iter()
+            .map(|node| {
+                self.labels.index(&node.label).ok_or_else(|| Error::Parse {
+                    path: path.to_string(),
+                    line: None,
+                    column: None,
+                    message: format!("unknown node {}", node.label),
+                })
+            })
+            .collect()
+    }
+
+    /// Prepares a contraction hierarchy, queried one source at a time with PHAST.
+    /// Reuses the hierarchy of a prepared graph. Fails on zero weights, which fast paths drop,
+    /// and on negative ones.
+    pub fn fast_path(&self) -> Result<PhastGraph, Error> {
+        self.check_non_negative()?;
+        match &self.fast_graph {
+            Some(fast_graph) => PhastGraph::from_fast_graph(fast_graph, self.num_nodes()),
+            None => PhastGraph::from_fast_graph(&self.prepare_fast_graph()?, self.num_nodes()),
+        }
+    }
+
+    fn prepare_fast_graph(&self) -> Result<FastGraph, Error> {
+        if self.has_zero_weights() {
+            return Err(Error::Invalid(
+                "zero weights are dropped by fast paths, use another algorithm such as dijkstra"
+                    .to_string(),
+            ));
+        }
+        let input_graph = into_input_graph(&self.edges, self.weight_scale, self.directed);
+        Ok(fast_paths::prepare(&input_graph))
+    }
+
+    /// Fails on negative weights.
+    pub fn dijkstra(&self) -> Result<DijkstraGraph, Error> {
+        self.check_non_negative()?;
+        Ok(into_dijkstra_graph(
+            self.num_nodes(),
+            &self.edges,
+            self.weight_scale,
+            self.directed,
+        ))
+    }
+
+    /// Ignores edge weights, counting every edge as a length of one.
+    pub fn bfs(&self) -> BfsGraph {
+        let unit_length = scale_weight(1.0, self.weight_scale);
+        into_bfs_graph(self.num_nodes(), &self.edges, self.directed, unit_length)
+    }
+
+    /// Computes every shortest path length as the graph is prepared. Fails on negative weights,
+    /// and on graphs too large for a matrix of every distance.
+    pub fn floyd_warshall(&self) -> Result<FloydWarshallGraph, Error> {
+        self.check_non_negative()?;
+        if self.num_nodes() > MAX_FLOYD_WARSHALL_MATRIX_NODES {
+            return Err(Error::Invalid(format!(
+                "floyd-warshall keeps a matrix of every distance, too large for {} nodes (at most \
+                 {}), use another algorithm such as fast-path",
+                self.num_nodes(),
+                MAX_FLOYD_WARSHALL_MATRIX_NODES
+            )));
+        }
+        Ok(into_floyd_warshall_graph(
+            self.num_nodes(),
+            &self.edges,
+            self.weight_scale,
+            self.directed,
+        ))
+    }
+
+    /// Resolves [`Algorithm::Auto`] for this graph: Johnson for negative weights, Floyd-Warshall
+    /// for small graphs dense enough that its `n^3` steps beat a Dijkstra search per source,
+    /// at about `m log n` each, and fast paths otherwise, unless zero weights leave Dijkstra.
+    /// Other algorithms are kept.
+    pub fn choose_algorithm(&self, algorithm: Algorithm) -> Algorithm {
+        if algorithm != Algorithm::Auto {
+            return algorithm;
+        }
+        if self.has_negative_weights() {
+            return Algorithm::Johnson;
+        }
+        let num_nodes = self.num_nodes();
+        let arcs = match self.directed {
+            true => self.edges.len(),
+            false => 2 * self.edges.len(),
+        };
+        // a Dijkstra step costs some 20 times a Floyd-Warshall one
+        let dijkstra_steps = 20.0 * arcs as f64 * (num_nodes.max(2) as f64).log2();
+        if num_nodes <= MAX_FLOYD_WARSHALL_NODES && (num_nodes as f64).powi(2) <= dijkstra_steps {
+            Algorithm::FloydWarshall
+        } else if self.has_zero_weights() {
+            Algorithm::Dijkstra
+        } else {
+            Algorithm::FastPath
+        }
+    }
+
+    fn has_negative_weights(&self) -> bool {
+        self.edges.iter().any(|edge| edge.weight < 0.0)
+    }
+
+    // Only Johnson's algorithm handles negative weights, which the others would take as 0.
+    fn check_non_negative(&self) -> Result<(), Error> {
+        match self.has_negative_weights() {
+            true => Err(Error::Invalid(
+                "negative weights need the johnson algorithm".to_string(),
+            )),
+            false => Ok(()),
+        }
+    }
+
+    // Weights are checked not to round to zero unless they are zero.
+    fn has_zero_weights(&self) -> bool {
+        self.edges.iter().any(|edge| edge.weight == 0.0)
+    }
+
+    /// Fails with the nodes of a negative cycle, in path order, when there is one.
+    pub fn johnson(&self) -> Result<JohnsonGraph, Error> {
+        into_johnson_graph(
+            self.num_nodes(),
+            &self.edges,
+            self.weight_scale,
+            self.directed,
+        )
+        .map_err(|cycle| {
+            let mut labels: Vec<&str> = cycle.iter().map(|&node| self.labels.label(node)).collect();
+            labels.push(labels[0]);
+            Error::Invalid(format!("negative cycle {}", labels.join(" -> ")))
+        })
+    }
+
+    pub fn prepare(&self, algorithm: Algorithm) -> Result<Box<dyn AllPairs>, Error> {
+        Ok(match self.choose_algorithm(algorithm) {
+            Algorithm::FastPath => Box::new(self.fast_path()?),
+            Algorithm::Dijkstra => Box::new(self.dijkstra()?),
+            Algorithm::Bfs => Box::new(self.bfs()),
+            Algorithm::Johnson => Box::new(self.johnson()?),
+            Algorithm::FloydWarshall => Box::new(self.floyd_warshall()?),
+            Algorithm::Auto => unreachable!("auto is resolved first"),
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    /// A path in the temp directory unique to this process, so concurrent test runs don't
+    /// share files.
+    pub(crate) fn temp_path(name: &str) -> String {
+        let name = format!("rust-shortest-path-{}-{}", std::process::id(), name);
+        std::env::temp_dir()
+            .join(name)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Every (src, dst, length) of `backend` in sorted order, searched by two threads.
+    pub(crate) fn lengths(backend: &dyn AllPairs) -> Vec<(usize, usize, i64)> {
+        let mut lengths = Vec::new();
+        backend
+            .shortest_paths(2, &mut |paths| {
+                lengths.extend(paths.iter().map(|path| (path.src, path.dst, path.length)));
+                Ok(())
+            })
+            .unwrap();
+        lengths.sort();
+        lengths
+    }
+
+    #[test]
+    fn names_parse_to_distinct_variants() {
+        fn check<T: FromStr<Err = String> + PartialEq + fmt::Debug>(names: &[&str]) {
+            let variants: Vec<T> = names.iter().map(|name| name.parse().unwrap()).collect();
+            for (i, variant) in variants.iter().enumerate() {
+                assert!(!variants[..i].contains(variant), "{:?}", variant);
+            }
+            assert!("nope".parse::<T>().unwrap_err().contains(&names.join(", ")));
+        }
+        check::<Algorithm>(Algorithm::NAMES);
+        check::<DuplicateEdges>(DuplicateEdges::NAMES);
+        check::<SelfLoops>(SelfLoops::NAMES);
+        check::<InputFormat>(InputFormat::NAMES);
+        check::<Delimiter>(Delimiter::NAMES);
+        check::<Binning>(Binning::NAMES);
+    }
+
+    #[test]
+    fn weight_scale_must_be_finite_and_positive() {
+        for weight_scale in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let mut builder = Graph::builder().weight_scale(weight_scale);
+            assert!(builder.add_edge("a", "b", 1.0).is_err(), "{}", weight_scale);
+        }
+    }
+
+    #[test]
+    fn weights_rounding_to_zero() {
+        let mut builder = Graph::builder();
+        assert!(builder.add_edge("a", "b", 0.4).is_err());
+        let mut builder = Graph::builder().weight_scale(10.0);
+        assert!(builder.add_edge("a", "b", 0.4).is_ok());
+    }
+
+    #[test]
+    fn labels_are_normalized() {
+        let mut builder = Graph::builder();
+        builder.add_edge("3.0", "4", 1.0).unwrap();
+        builder.add_node("3").unwrap();
+        builder.add_node("4.0").unwrap();
+        assert!(builder.add_node("100000000000000000000.0").is_err());
+        let graph = builder.build();
+        assert_eq!(graph.num_nodes(), 2);
+        assert_eq!(graph.labels().label(0), "3");
+    }
+
+    #[test]
+    fn zero_weights() {
+        let mut builder = Graph::builder();
+        builder.add_edge("a", "b", 0.0).unwrap();
+        builder.add_edge("b", "c", 3.0).unwrap();
+        let graph = builder.build();
+        assert!(graph.prepare(Algorithm::FastPath).is_err());
+        let expected = lengths(&graph.dijkstra().unwrap());
+        assert!(expected.contains(&(0, 1, 0)) && expected.contains(&(0, 2, 3)));
+        for algorithm in [
+            Algorithm::Johnson,
+            Algorithm::FloydWarshall,
+            Algorithm::Auto,
+        ] {
+            assert_eq!(lengths(&*graph.prepare(algorithm).unwrap()), expected);
+        }
+    }
+
+    fn weights(builder: GraphBuilder, edges: &[(&str, &str, f32)]) -> Result<Vec<f32>, Error> {
+        let mut builder = builder;
+        for &(src, dst, weight) in edges {
+            builder.add_edge(src, dst, weight)?;
+        }
+        Ok(builder
+            .build()
+            .edges
+            .iter()
+            .map(|edge| edge.weight)
+            .collect())
+    }
+
+    #[test]
+    fn duplicate_edges() {
+        let edges = [("a", "b", 3.0), ("b", "a", 1.0), ("a", "b", 2.0)];
+        for (policy, expected) in [
+            (DuplicateEdges::Min, 1.0),
+            (DuplicateEdges::Max, 3.0),
+            (DuplicateEdges::Sum, 6.0),
+            (DuplicateEdges::First, 3.0),
+        ] {
+            let builder = Graph::builder().duplicate_edges(policy);
+            assert_eq!(
+                weights(builder, &edges).unwrap(),
+                vec![expected],
+                "{:?}",
+                policy
+            );
+        }
+        let builder = Graph::builder().duplicate_edges(DuplicateEdges::Error);
+        let error = weights(builder, &edges).err().unwrap();
+        assert_eq!(error.to_string(), "duplicate edge b -- a");
+        // only the same direction is a duplicate of a directed edge
+        let builder = Graph::builder()
+            .directed(true)
+            .duplicate_edges(DuplicateEdges::Error);
+        assert_eq!(weights(builder, &edges[..2]).unwrap(), vec![3.0, 1.0]);
+    }
+
+    #[test]
+    fn duplicate_edge_error_line() {
+        let path = temp_path("duplicates.csv");
+        std::fs::write(&path, "a,b,1\nb,c,1\n\nb,a,2\n").unwrap();
+        let mut builder = Graph::builder().duplicate_edges(DuplicateEdges::Error);
+        let error = builder
+            .read_edges(&path, &EdgeListOptions::default())
+            .err()
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            error.to_string(),
+            format!("{}:4: duplicate edge b -- a", path)
+        );
+    }
+
+    #[test]
+    fn self_loops() {
+        let edges = [("a", "a", 1.0), ("a", "b", 1.0)];
+        assert_eq!(weights(Graph::builder(), &edges).unwrap(), vec![1.0]);
+        let builder = Graph::builder().self_loops(SelfLoops::Error);
+        let error = weights(builder, &edges).err().unwrap();
+        assert_eq!(error.to_string(), "self-loop on a");
+        // dropping a negative self-loop would hide a negative cycle
+        let builder = Graph::builder().negative_weights(true);
+        let error = weights(builder, &[("a", "a", -1.0)]).err().unwrap();
+        assert_eq!(error.to_string(), "negative cycle a -> a");
+    }
+
+    #[test]
+    fn edge_summary() {
+        let mut builder = Graph::builder().duplicate_edges(DuplicateEdges::Sum);
+        for (src, dst) in [("a", "b"), ("b", "a"), ("a", "a"), ("b", "c"), ("c", "b")] {
+            builder.add_edge(src, dst, 1.0).unwrap();
+        }
+        let summary = builder.build().summary().clone();
+        assert_eq!(
+            (summary.edges, summary.duplicate_edges, summary.self_loops),
+            (5, 2, 1)
+        );
+        assert_eq!(
+            summary.to_string(),
+            "5 edges, 2 duplicate edges merged, 1 self-loops dropped"
+        );
+    }
+}
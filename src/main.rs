@@ -1,162 +1,520 @@
-use std::fmt;
+use std::env;
+use std::ffi::OsString;
+use std::num::NonZeroUsize;
+use std::process;
 
-use clap::{ArgEnum, Parser};
-use csv::{self, StringRecord};
-use fast_paths::{FastGraph, InputGraph};
-use pathfinder::PathfinderGraph;
-use serde::{self, de, Deserialize, Deserializer};
-
-use crate::pathfinder::into_pathfinder_graph;
-
-mod pathfinder;
-
-const MAX_WEIGHT_VALUE: f32 = 4294967296_f32;
+use clap::parser::ValueSource;
+use clap::{ArgEnum, ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use rust_shortest_path::output::{
+    write_distance_matrix, write_labels, write_portrait, write_portrait_matrix,
+    write_shortest_paths, write_triples,
+};
+use rust_shortest_path::{
+    is_prepared, portrait_divergence, sample_sources, shared_bin_edges, Algorithm, AllPairs,
+    Binning, Column, Delimiter, DuplicateEdges, EdgeListOptions, Error, Graph, InputFormat,
+    Portrait, SelfLoops, Subset,
+};
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
-struct Args {
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Computes all-pairs shortest path lengths or the network portrait of a graph. Also the
+    /// default command, taking the same arguments without its name
+    Paths(PathsArgs),
+    /// Computes the portrait divergence between two graphs
+    Divergence(DivergenceArgs),
+    /// Saves a graph with its contraction hierarchy, so later runs skip preparing it. The
+    /// prepared graph keeps the options it was prepared with, and is refused once its edge
+    /// list changes
+    Prepare(PrepareArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct PathsArgs {
+    /// Graph file, prepared graph, or `-` for stdin; `.gz` and `.zst` files are decompressed
     #[clap(short, long)]
     input: String,
 
+    /// Output file, or `-` for stdout; `.gz` and `.zst` files are compressed
     #[clap(short, long)]
     output: String,
 
-    #[clap(short, long, arg_enum, default_value = "fast-path")]
-    algorithm: Algorithm,
+    /// File listing node labels, one per line, so isolated nodes are included
+    #[clap(long)]
+    nodes: Option<String>,
+
+    #[clap(short, long, arg_enum, default_value = "pairs")]
+    mode: OutputMode,
+
+    #[clap(long, arg_enum, default_value = "csv")]
+    output_format: OutputFormat,
+
+    /// Write the node labels, one per line, to name the rows of binary outputs: the sources of
+    /// a matrix in row order, or every node in index order otherwise
+    #[clap(long)]
+    labels_output: Option<String>,
+
+    /// Write the labels of the columns of a matrix, one per line: its targets, or every node
+    #[clap(long)]
+    column_labels_output: Option<String>,
+
+    #[clap(flatten)]
+    edges: EdgeListArgs,
+
+    #[clap(flatten)]
+    graph: GraphArgs,
+
+    #[clap(flatten)]
+    search: SearchArgs,
+
+    #[clap(flatten)]
+    subset: SubsetArgs,
+
+    #[clap(flatten)]
+    binning: BinningArgs,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug)]
-enum Algorithm {
-    Dijkstra,
-    FastPath,
+#[derive(clap::Args, Debug)]
+struct DivergenceArgs {
+    first: String,
+
+    second: String,
+
+    /// Node list for the first graph, so isolated nodes are included
+    #[clap(long)]
+    first_nodes: Option<String>,
+
+    /// Node list for the second graph, so isolated nodes are included
+    #[clap(long)]
+    second_nodes: Option<String>,
+
+    #[clap(flatten)]
+    edges: EdgeListArgs,
+
+    #[clap(flatten)]
+    graph: GraphArgs,
+
+    #[clap(flatten)]
+    search: SearchArgs,
+
+    #[clap(flatten)]
+    binning: BinningArgs,
 }
 
-#[derive(Clone, Debug, Deserialize)]
-struct WeightedNodes {
-    #[serde(deserialize_with = "deserialize_int_or_float")]
-    src: u64,
-    #[serde(deserialize_with = "deserialize_int_or_float")]
-    dst: u64,
-    weight: f32,
+#[derive(clap::Args, Debug)]
+struct PrepareArgs {
+    /// Edge list file; `.gz` and `.zst` files are decompressed
+    #[clap(short, long)]
+    input: String,
+
+    /// Prepared graph file, to pass as the input of the other commands
+    #[clap(short, long)]
+    output: String,
+
+    /// File listing node labels, one per line, so isolated nodes are included
+    #[clap(long)]
+    nodes: Option<String>,
+
+    #[clap(flatten)]
+    edges: EdgeListArgs,
+
+    #[clap(flatten)]
+    graph: GraphArgs,
 }
 
-#[derive(Clone, Debug)]
-struct ShortestPathLength {
-    src: usize,
-    dst: usize,
-    length: usize,
+#[derive(clap::Args, Debug)]
+struct GraphArgs {
+    /// Fixed-point scale applied to edge weights before they are rounded to integers
+    #[clap(long, default_value = "1", parse(try_from_str = parse_weight_scale))]
+    weight_scale: f64,
+
+    /// Treat edges as one-way from the source to the target column
+    #[clap(long)]
+    directed: bool,
+
+    /// How to merge edges between the same nodes
+    #[clap(long, default_value = "min", possible_values = DuplicateEdges::NAMES)]
+    duplicate_edges: DuplicateEdges,
+
+    /// Whether to drop edges from a node to itself or reject them
+    #[clap(long, default_value = "drop", possible_values = SelfLoops::NAMES)]
+    self_loops: SelfLoops,
 }
 
-fn into_input_graph(weighted_nodes: Vec<WeightedNodes>) -> InputGraph {
-    let mut input_graph = InputGraph::new();
-    for node in weighted_nodes.iter() {
-        input_graph.add_edge_bidir(node.src as usize, node.dst as usize, (node.weight) as usize);
+#[derive(clap::Args, Debug)]
+struct EdgeListArgs {
+    /// Format of the graph files, guessed from their extension by default
+    #[clap(long, possible_values = InputFormat::NAMES)]
+    input_format: Option<InputFormat>,
+
+    /// Treat the first row of edge lists as a header instead of an edge
+    #[clap(long)]
+    has_header: bool,
+
+    /// Source column, by 0-based index or header name
+    #[clap(long, default_value = "0")]
+    source_column: Column,
+
+    /// Target column, by 0-based index or header name
+    #[clap(long, default_value = "1")]
+    target_column: Column,
+
+    /// Weight column, by 0-based index or header name
+    #[clap(long, default_value = "2")]
+    weight_column: Column,
+
+    /// Weight of edges that don't have one
+    #[clap(long, default_value = "1")]
+    default_weight: f32,
+
+    /// Give every edge the default weight, whatever the file says
+    #[clap(long)]
+    ignore_weights: bool,
+
+    /// Field separator of edge lists; whitespace is any run of spaces and tabs, as in SNAP and
+    /// KONECT edge lists
+    #[clap(long, default_value = "comma", possible_values = Delimiter::NAMES)]
+    delimiter: Delimiter,
+
+    /// Skip lines starting with this character
+    #[clap(long)]
+    comment: Option<char>,
+}
+
+impl EdgeListArgs {
+    fn options(&self) -> EdgeListOptions {
+        EdgeListOptions {
+            input_format: self.input_format,
+            has_header: self.has_header,
+            source_column: self.source_column.clone(),
+            target_column: self.target_column.clone(),
+            weight_column: self.weight_column.clone(),
+            default_weight: self.default_weight,
+            ignore_weights: self.ignore_weights,
+            delimiter: self.delimiter,
+            comment: self.comment,
+        }
     }
-    input_graph.freeze();
-    input_graph
 }
 
-struct DeserializeFloatOrIntegerVisitor;
+#[derive(clap::Args, Debug)]
+struct SearchArgs {
+    /// Johnson supports negative weights but not negative cycles, Floyd-Warshall computes a
+    /// dense matrix up front for small and dense graphs, and auto picks one to suit the graph
+    #[clap(short, long, default_value = "fast-path", possible_values = Algorithm::NAMES)]
+    algorithm: Algorithm,
 
-impl<'de> de::Visitor<'de> for DeserializeFloatOrIntegerVisitor {
-    type Value = u64;
+    /// Number of worker threads sharing the shortest path sources
+    #[clap(long, default_value = "1")]
+    threads: NonZeroUsize,
+}
 
-    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("an integer or a float")
-    }
+#[derive(clap::Args, Debug)]
+struct SubsetArgs {
+    /// File listing the nodes to search from, one per line, instead of every node
+    #[clap(long)]
+    sources: Option<String>,
+
+    /// File listing the only nodes to report paths to, one per line, which searches stop at
+    /// once they reach them all. Not allowed for portraits
+    #[clap(long)]
+    targets: Option<String>,
+
+    /// Search from this many nodes picked at random instead of every node
+    #[clap(long, conflicts_with = "sources")]
+    sample_sources: Option<usize>,
+
+    /// Seed picking the sampled sources, which are the same for the same seed
+    #[clap(long, default_value = "0", requires = "sample-sources")]
+    seed: u64,
+}
+
+#[derive(clap::Args, Debug)]
+struct BinningArgs {
+    /// Bin path lengths into this many bins, building a weighted portrait
+    #[clap(long)]
+    bins: Option<NonZeroUsize>,
+
+    #[clap(long, default_value = "quantile", possible_values = Binning::NAMES)]
+    binning: Binning,
+}
 
-    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        Ok(v)
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug)]
+enum OutputMode {
+    Pairs,
+    Portrait,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug)]
+enum OutputFormat {
+    Csv,
+    /// Dense `.npy` matrix of path lengths, infinite for unreachable pairs
+    Matrix,
+    /// `.npy` array of (src, dst, length) records with node indices
+    Triples,
+}
+
+fn parse_weight_scale(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(weight_scale) if weight_scale.is_finite() && weight_scale > 0.0 => Ok(weight_scale),
+        Ok(_) => Err("must be finite and positive".to_string()),
+        Err(error) => Err(error.to_string()),
     }
+}
 
-    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        Ok(v as u64)
+fn build_graph(
+    input: &str,
+    nodes: Option<&str>,
+    edge_options: &EdgeListOptions,
+    args: &GraphArgs,
+    negative_weights: bool,
+) -> Result<Graph, Error> {
+    let mut builder = Graph::builder()
+        .directed(args.directed)
+        .weight_scale(args.weight_scale)
+        .duplicate_edges(args.duplicate_edges)
+        .self_loops(args.self_loops)
+        .negative_weights(negative_weights);
+    builder.read_edges(input, edge_options)?;
+    if let Some(nodes) = nodes {
+        builder.read_nodes(nodes)?;
+    }
+    let graph = builder.build();
+    let summary = graph.summary();
+    if summary.duplicate_edges > 0 || summary.self_loops > 0 {
+        eprintln!("{}: {}", input, summary);
     }
+    Ok(graph)
 }
 
-fn deserialize_int_or_float<'de, D>(deserializer: D) -> Result<u64, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    deserializer.deserialize_any(DeserializeFloatOrIntegerVisitor)
-}
-
-fn read_weighted_nodes(path: &str) -> Vec<WeightedNodes> {
-    let mut reader = csv::Reader::from_path(path).unwrap();
-    reader.set_headers(StringRecord::from(vec!["src", "dst", "weight"]));
-    // for result in reader.records() {
-    //     println!("{:?}", result.unwrap());
-    // }
-    reader.deserialize().map(|x| x.unwrap()).collect()
-}
-
-fn all_pairs_path_length(graph: FastGraph) -> Vec<ShortestPathLength> {
-    let mut res = Vec::with_capacity(graph.get_num_nodes() * graph.get_num_nodes());
-    let mut path_calculator = fast_paths::create_calculator(&graph);
-    for src in 0..graph.get_num_nodes() {
-        for dst in 0..graph.get_num_nodes() {
-            match path_calculator.calc_path(&graph, src, dst) {
-                Some(path) => res.push(ShortestPathLength {
-                    src,
-                    dst,
-                    length: path.get_weight(),
-                }),
-                None => (),
-            }
+// Graph and edge list options given on the command line rather than left at their defaults.
+fn explicit_graph_options(matches: &ArgMatches) -> Vec<String> {
+    let options = EdgeListArgs::augment_args(GraphArgs::augment_args(clap::Command::new("")));
+    options
+        .get_arguments()
+        // skips the help and version flags, which the subcommand doesn't have
+        .filter(|option| matches.try_get_raw(option.get_id()).is_ok())
+        .filter(|option| matches.value_source(option.get_id()) == Some(ValueSource::CommandLine))
+        .filter_map(|option| option.get_long())
+        .map(|long| format!("--{}", long))
+        .collect()
+}
+
+fn read_graph(
+    input: &str,
+    nodes: Option<&str>,
+    edge_options: &EdgeListOptions,
+    args: &GraphArgs,
+    search: &SearchArgs,
+    explicit: &[String],
+) -> Result<(Graph, Box<dyn AllPairs>), Error> {
+    let graph = match is_prepared(input) {
+        true if nodes.is_some() => {
+            return Err(Error::Invalid(format!(
+                "{} is a prepared graph, which already includes its nodes",
+                input
+            )))
+        }
+        true if !explicit.is_empty() => {
+            return Err(Error::Invalid(format!(
+                "{} is a prepared graph, which keeps the options it was prepared with, so {} \
+                 can't be given",
+                input,
+                explicit.join(", ")
+            )))
+        }
+        true => Graph::read_prepared(input)?,
+        false => {
+            let negative_weights = matches!(search.algorithm, Algorithm::Johnson | Algorithm::Auto);
+            build_graph(input, nodes, edge_options, args, negative_weights)?
         }
+    };
+    let backend = graph.prepare(search.algorithm)?;
+    Ok((graph, backend))
+}
+
+fn bin_edges_for(
+    backends: &[&dyn AllPairs],
+    search: &SearchArgs,
+    binning: &BinningArgs,
+) -> Result<Option<Vec<f64>>, Error> {
+    match binning.bins {
+        Some(bins) => {
+            let edges =
+                shared_bin_edges(backends, bins.get(), binning.binning, search.threads.get())?;
+            Ok(Some(edges))
+        }
+        None => Ok(None),
     }
-    res
 }
 
-fn all_pairs_path_length_pathfinder(graph: PathfinderGraph) -> Vec<ShortestPathLength> {
-    let mut res = Vec::with_capacity(graph.num_nodes * graph.num_nodes);
+fn subset<'a>(
+    graph: &Graph,
+    backend: &'a dyn AllPairs,
+    args: &SubsetArgs,
+) -> Result<Subset<'a>, Error> {
+    let sources = match (&args.sources, args.sample_sources) {
+        (Some(sources), _) => Some(graph.read_node_indices(sources)?),
+        (None, Some(count)) => Some(sample_sources(graph.num_nodes(), count, args.seed)),
+        (None, None) => None,
+    };
+    let targets = match &args.targets {
+        Some(targets) => Some(graph.read_node_indices(targets)?),
+        None => None,
+    };
+    Ok(Subset::new(backend, sources, targets.as_deref()))
+}
 
-    for src in 0..graph.num_nodes {
-        for (dst, (_, weight)) in graph.all_paths_for_node(src) {
-            res.push(ShortestPathLength {
-                src,
-                dst,
-                length: weight,
-            })
+fn run_paths(args: PathsArgs, explicit: &[String]) -> Result<(), Error> {
+    let (graph, backend) = read_graph(
+        &args.input,
+        args.nodes.as_deref(),
+        &args.edges.options(),
+        &args.graph,
+        &args.search,
+        explicit,
+    )?;
+    let subset = subset(&graph, &*backend, &args.subset)?;
+    let backend: &dyn AllPairs = &subset;
+    let matrix = args.mode == OutputMode::Pairs && args.output_format == OutputFormat::Matrix;
+    if let Some(labels_output) = &args.labels_output {
+        // the other outputs name nodes by index
+        let rows = match matrix {
+            true => backend.sources(),
+            false => (0..graph.num_nodes()).collect(),
+        };
+        write_labels(labels_output, graph.labels(), &rows)?;
+    }
+    if let Some(column_labels_output) = &args.column_labels_output {
+        if !matrix {
+            return Err(Error::Invalid(
+                "only a distance matrix has column labels".to_string(),
+            ));
         }
+        write_labels(column_labels_output, graph.labels(), &backend.targets())?;
     }
-    res
+    let output = &args.output;
+    let threads = args.search.threads.get();
+    match (args.mode, args.output_format) {
+        (OutputMode::Pairs, OutputFormat::Csv) => {
+            write_shortest_paths(output, &graph, backend, threads)
+        }
+        (OutputMode::Pairs, OutputFormat::Matrix) => {
+            write_distance_matrix(output, &graph, backend, threads)
+        }
+        (OutputMode::Pairs, OutputFormat::Triples) => {
+            write_triples(output, &graph, backend, threads)
+        }
+        (OutputMode::Portrait, OutputFormat::Triples) => Err(Error::Invalid(
+            "portraits can only be written as csv or a matrix".to_string(),
+        )),
+        // a portrait counts every node at each length, so it can't leave out the other nodes
+        (OutputMode::Portrait, _) if args.subset.targets.is_some() => Err(Error::Invalid(
+            "portraits count paths to every node, so --targets can't be given".to_string(),
+        )),
+        (OutputMode::Portrait, format) => {
+            let edges = bin_edges_for(&[backend], &args.search, &args.binning)?;
+            let portrait =
+                Portrait::of_graph(backend, edges.as_deref(), graph.weight_scale(), threads)?;
+            match format {
+                OutputFormat::Matrix => write_portrait_matrix(output, &portrait),
+                _ => write_portrait(output, &portrait),
+            }
+        }
+    }
+}
+
+fn run_divergence(args: DivergenceArgs, explicit: &[String]) -> Result<(), Error> {
+    let (first_graph, first) = read_graph(
+        &args.first,
+        args.first_nodes.as_deref(),
+        &args.edges.options(),
+        &args.graph,
+        &args.search,
+        explicit,
+    )?;
+    let (second_graph, second) = read_graph(
+        &args.second,
+        args.second_nodes.as_deref(),
+        &args.edges.options(),
+        &args.graph,
+        &args.search,
+        explicit,
+    )?;
+    // bins are shared by scaled lengths, while prepared graphs can each have their own scale
+    if args.binning.bins.is_some() && first_graph.weight_scale() != second_graph.weight_scale() {
+        return Err(Error::Invalid(format!(
+            "binned portraits need the same weight scale, but {} has {} and {} has {}",
+            args.first,
+            first_graph.weight_scale(),
+            args.second,
+            second_graph.weight_scale()
+        )));
+    }
+    let threads = args.search.threads.get();
+    // both portraits must share the same bins to be comparable
+    let edges = bin_edges_for(&[&*first, &*second], &args.search, &args.binning)?;
+    let first = Portrait::of_graph(
+        &*first,
+        edges.as_deref(),
+        first_graph.weight_scale(),
+        threads,
+    )?;
+    let second = Portrait::of_graph(
+        &*second,
+        edges.as_deref(),
+        second_graph.weight_scale(),
+        threads,
+    )?;
+    println!("{}", portrait_divergence(&first, &second));
+    Ok(())
 }
 
-fn write_shortest_paths(output: &str, paths: Vec<ShortestPathLength>) {
-    let mut writer = csv::Writer::from_path(output).unwrap();
+fn run_prepare(args: PrepareArgs) -> Result<(), Error> {
+    let graph = build_graph(
+        &args.input,
+        args.nodes.as_deref(),
+        &args.edges.options(),
+        &args.graph,
+        false,
+    )?;
+    graph.write_prepared(&args.output, &args.input)
+}
 
-    for path in paths {
-        writer
-            .write_record(&[
-                path.src.to_string(),
-                path.dst.to_string(),
-                (path.length as f32).to_string(),
-            ])
-            .unwrap();
+// Arguments that don't start with a command are those of `paths`, which was the only command
+// before there were subcommands.
+fn args() -> Vec<OsString> {
+    let mut args: Vec<OsString> = env::args_os().collect();
+    let named = match args.get(1).and_then(|arg| arg.to_str()) {
+        Some("help" | "-h" | "--help" | "-V" | "--version") | None => true,
+        Some(arg) => Cli::command().find_subcommand(arg).is_some(),
+    };
+    if !named {
+        args.insert(1, "paths".into());
     }
+    args
 }
 
 fn main() {
-    let args = Args::parse();
-    let paths = match args.algorithm {
-        Algorithm::FastPath => {
-            let nodes = read_weighted_nodes(&args.input);
-            let input_graph = into_input_graph(nodes);
-            let fast_graph = fast_paths::prepare(&input_graph);
-            all_pairs_path_length(fast_graph)
-        }
-        Algorithm::Dijkstra => {
-            let nodes = read_weighted_nodes(&args.input);
-            let graph = into_pathfinder_graph(nodes);
-            all_pairs_path_length_pathfinder(graph)
-        }
+    let matches = Cli::command().get_matches_from(args());
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+    let explicit = match matches.subcommand() {
+        Some((_, matches)) => explicit_graph_options(matches),
+        None => Vec::new(),
     };
-    write_shortest_paths(&args.output, paths)
+    let result = match cli.command {
+        Command::Paths(args) => run_paths(args, &explicit),
+        Command::Divergence(args) => run_divergence(args, &explicit),
+        Command::Prepare(args) => run_prepare(args),
+    };
+    if let Err(error) = result {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
 }
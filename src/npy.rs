@@ -0,0 +1,66 @@
+//! Writes NumPy `.npy` arrays, which numpy can load or memory-map without a copy.
+
+// Reserved for the row count of arrays whose length is only known once they are written.
+const SHAPE_WIDTH: usize = 20;
+
+/// The header of a version 1.0 `.npy` file holding a C-ordered array of `descr` elements with
+/// the given `shape`. Padded so the data starts on a 64 byte boundary, as the format asks.
+pub(crate) fn header(descr: &str, shape: &[usize]) -> Vec<u8> {
+    let shape = match shape {
+        [len] => format!("({:<width$},)", len, width = SHAPE_WIDTH),
+        _ => {
+            let dims: Vec<String> = shape.iter().map(|dim| dim.to_string()).collect();
+            format!("({})", dims.join(", "))
+        }
+    };
+    let mut dict = format!(
+        "{{'descr': {}, 'fortran_order': False, 'shape': {}, }}",
+        descr, shape
+    );
+    let unpadded = 6 + 2 + 2 + dict.len() + 1;
+    dict.extend(std::iter::repeat_n(' ', (64 - unpadded % 64) % 64));
+    dict.push('\n');
+
+    let mut header = b"\x93NUMPY\x01\x00".to_vec();
+    header.extend_from_slice(&(dict.len() as u16).to_le_bytes());
+    header.extend_from_slice(dict.as_bytes());
+    header
+}
+
+/// A single float, with infinity for unreachable pairs.
+pub(crate) const FLOAT: &str = "'<f4'";
+
+pub(crate) const UNSIGNED: &str = "'<u8'";
+
+/// Records of two node indices and the length of the shortest path between them.
+pub(crate) const TRIPLE: &str = "[('src', '<u4'), ('dst', '<u4'), ('length', '<f4')]";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_are_aligned() {
+        for (descr, shape) in [
+            (FLOAT, &[3, 4][..]),
+            (UNSIGNED, &[1_000_000, 70][..]),
+            (TRIPLE, &[0][..]),
+            (TRIPLE, &[123_456_789][..]),
+        ] {
+            let header = header(descr, shape);
+            assert_eq!(header.len() % 64, 0, "{} {:?}", descr, shape);
+            assert_eq!(header.last(), Some(&b'\n'));
+            let len = u16::from_le_bytes([header[8], header[9]]) as usize;
+            assert_eq!(header.len(), 10 + len);
+        }
+    }
+
+    // The triples header is written again over the placeholder once the row count is known.
+    #[test]
+    fn row_count_fits_the_placeholder() {
+        let placeholder = header(TRIPLE, &[0]);
+        for count in [1, 12_345, u32::MAX as usize, usize::MAX] {
+            assert_eq!(header(TRIPLE, &[count]).len(), placeholder.len());
+        }
+    }
+}
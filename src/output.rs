@@ -0,0 +1,238 @@
+//! Writers for the shortest paths and portraits of a graph. Outputs ending in `.gz` or `.zst`
+//! are compressed, and `-` is stdout.
+
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+
+use crate::error::Error;
+use crate::labels::NodeLabels;
+use crate::npy;
+use crate::portrait::Portrait;
+use crate::stream::{self, Output};
+use crate::{AllPairs, Graph};
+
+fn csv_writer(output: &str) -> Result<csv::Writer<Output>, Error> {
+    let output = Output::create(output).map_err(|e| Error::io(output, e))?;
+    Ok(csv::Writer::from_writer(output))
+}
+
+fn finish_csv(output: &str, writer: csv::Writer<Output>) -> Result<(), Error> {
+    let writer = writer
+        .into_inner()
+        .map_err(|e| Error::io(output, e.into_error()))?;
+    writer.finish().map_err(|e| Error::io(output, e))
+}
+
+/// Writes a csv row of source, target and length for every shortest path.
+pub fn write_shortest_paths(
+    output: &str,
+    graph: &Graph,
+    backend: &dyn AllPairs,
+    threads: usize,
+) -> Result<(), Error> {
+    let labels = graph.labels();
+    let mut writer = csv_writer(output)?;
+
+    backend.shortest_paths(threads, &mut |paths| {
+        for path in paths {
+            writer
+                .write_record(&[
+                    labels.label(path.src).to_string(),
+                    labels.label(path.dst).to_string(),
+                    ((path.length as f64 / graph.weight_scale()) as f32).to_string(),
+                ])
+                .map_err(|e| Error::csv(output, None, e))?;
+        }
+        Ok(())
+    })?;
+    finish_csv(output, writer)
+}
+
+/// Writes a dense `.npy` matrix of path lengths, infinite for unreachable pairs. Has a row per
+/// source and a column per target, in the orders of [`AllPairs::sources`] and
+/// [`AllPairs::targets`].
+pub fn write_distance_matrix(
+    output: &str,
+    graph: &Graph,
+    backend: &dyn AllPairs,
+    threads: usize,
+) -> Result<(), Error> {
+    let targets = backend.targets();
+    let mut columns = vec![None; backend.num_nodes()];
+    for (column, &target) in targets.iter().enumerate() {
+        columns[target] = Some(column);
+    }
+    let mut writer = Output::create(output).map_err(|e| Error::io(output, e))?;
+    writer
+        .write_all(&npy::header(
+            npy::FLOAT,
+            &[backend.num_sources(), targets.len()],
+        ))
+        .map_err(|e| Error::io(output, e))?;
+
+    let mut row = vec![f32::INFINITY; targets.len()];
+    let mut bytes = Vec::with_capacity(targets.len() * 4);
+    backend.shortest_paths(threads, &mut |paths| {
+        row.fill(f32::INFINITY);
+        for path in paths {
+            if let Some(column) = columns[path.dst] {
+                row[column] = (path.length as f64 / graph.weight_scale()) as f32;
+            }
+        }
+        bytes.clear();
+        bytes.extend(row.iter().flat_map(|length| length.to_le_bytes()));
+        writer.write_all(&bytes).map_err(|e| Error::io(output, e))
+    })?;
+    writer.finish().map_err(|e| Error::io(output, e))
+}
+
+/// Writes a `.npy` array of (src, dst, length) records with node indices. The row count is
+/// only known at the end, so the header is written again once it is.
+pub fn write_triples(
+    output: &str,
+    graph: &Graph,
+    backend: &dyn AllPairs,
+    threads: usize,
+) -> Result<(), Error> {
+    if output == "-" || stream::uncompressed_path(output) != output {
+        return Err(Error::Invalid(
+            "triples can only be written to an uncompressed file".to_string(),
+        ));
+    }
+    let file = File::create(output).map_err(|e| Error::io(output, e))?;
+    let mut writer = BufWriter::new(file);
+    writer
+        .write_all(&npy::header(npy::TRIPLE, &[0]))
+        .map_err(|e| Error::io(output, e))?;
+
+    let mut count = 0;
+    backend.shortest_paths(threads, &mut |paths| {
+        for path in paths {
+            let length = (path.length as f64 / graph.weight_scale()) as f32;
+            let mut record = [0; 12];
+            record[..4].copy_from_slice(&(path.src as u32).to_le_bytes());
+            record[4..8].copy_from_slice(&(path.dst as u32).to_le_bytes());
+            record[8..].copy_from_slice(&length.to_le_bytes());
+            writer
+                .write_all(&record)
+                .map_err(|e| Error::io(output, e))?;
+        }
+        count += paths.len();
+        Ok(())
+    })?;
+    let mut file = writer
+        .into_inner()
+        .map_err(|e| Error::io(output, e.into_error()))?;
+    file.seek(SeekFrom::Start(0))
+        .and_then(|_| file.write_all(&npy::header(npy::TRIPLE, &[count])))
+        .map_err(|e| Error::io(output, e))
+}
+
+/// Writes the labels of `nodes` one per line in the order given, to name the rows or columns
+/// of binary outputs.
+pub fn write_labels(output: &str, labels: &NodeLabels, nodes: &[usize]) -> Result<(), Error> {
+    let mut writer = csv_writer(output)?;
+    for &node in nodes {
+        writer
+            .write_record([labels.label(node)])
+            .map_err(|e| Error::csv(output, None, e))?;
+    }
+    finish_csv(output, writer)
+}
+
+pub fn write_portrait_matrix(output: &str, portrait: &Portrait) -> Result<(), Error> {
+    let matrix = portrait.matrix();
+    let shape = [matrix.len(), matrix.first().map_or(0, Vec::len)];
+    let mut writer = Output::create(output).map_err(|e| Error::io(output, e))?;
+    writer
+        .write_all(&npy::header(npy::UNSIGNED, &shape))
+        .map_err(|e| Error::io(output, e))?;
+    for count in matrix.iter().flatten() {
+        writer
+            .write_all(&(*count as u64).to_le_bytes())
+            .map_err(|e| Error::io(output, e))?;
+    }
+    writer.finish().map_err(|e| Error::io(output, e))
+}
+
+pub fn write_portrait(output: &str, portrait: &Portrait) -> Result<(), Error> {
+    let mut writer = csv_writer(output)?;
+
+    for row in portrait.matrix() {
+        writer
+            .write_record(row.iter().map(|count| count.to_string()))
+            .map_err(|e| Error::csv(output, None, e))?;
+    }
+    finish_csv(output, writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::temp_path;
+    use crate::Subset;
+
+    fn read_floats(path: &str) -> Vec<f32> {
+        let bytes = std::fs::read(path).unwrap();
+        let data = 10 + u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        bytes[data..]
+            .chunks(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn distance_matrix_of_subset() {
+        let mut builder = Graph::builder();
+        for (src, dst) in [("a", "b"), ("b", "c"), ("c", "d")] {
+            builder.add_edge(src, dst, 1.0).unwrap();
+        }
+        let graph = builder.build();
+        let dijkstra = graph.dijkstra().unwrap();
+        let subset = Subset::new(&dijkstra, Some(vec![3, 0]), Some(&[2, 1, 2]));
+        let output = temp_path("subset.npy");
+        write_distance_matrix(&output, &graph, &subset, 1).unwrap();
+        let header = npy::header(npy::FLOAT, &[2, 2]);
+        assert!(std::fs::read(&output).unwrap().starts_with(&header));
+        assert_eq!(read_floats(&output), vec![2.0, 1.0, 1.0, 2.0]);
+        std::fs::remove_file(output).unwrap();
+    }
+
+    #[test]
+    fn triples() {
+        let mut builder = Graph::builder().directed(true).weight_scale(2.0);
+        builder.add_edge("a", "b", 1.5).unwrap();
+        builder.add_edge("b", "c", 2.0).unwrap();
+        let graph = builder.build();
+        let output = temp_path("triples.npy");
+        write_triples(&output, &graph, &graph.dijkstra().unwrap(), 1).unwrap();
+        let bytes = std::fs::read(&output).unwrap();
+        std::fs::remove_file(output).unwrap();
+
+        let header = npy::header(npy::TRIPLE, &[6]);
+        assert!(bytes.starts_with(&header));
+        let records: Vec<(u32, u32, f32)> = bytes[header.len()..]
+            .chunks_exact(12)
+            .map(|record| {
+                let field = |i: usize| record[i..i + 4].try_into().unwrap();
+                (
+                    u32::from_le_bytes(field(0)),
+                    u32::from_le_bytes(field(4)),
+                    f32::from_le_bytes(field(8)),
+                )
+            })
+            .collect();
+        assert_eq!(bytes.len(), header.len() + 12 * records.len());
+        assert_eq!(
+            records,
+            vec![
+                (0, 0, 0.0),
+                (0, 1, 1.5),
+                (0, 2, 3.5),
+                (1, 1, 0.0),
+                (1, 2, 2.0),
+                (2, 2, 0.0)
+            ]
+        );
+    }
+}
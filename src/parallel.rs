@@ -0,0 +1,118 @@
+use std::any::Any;
+use std::sync::mpsc;
+use std::thread;
+
+use crate::error::Error;
+use crate::ShortestPathLength;
+
+/// Runs `source` for every node of `sources` and hands each source's paths to `sink` in the
+/// order given, so the output is identical whatever the number of threads. Sources are dealt
+/// round-robin to `threads` workers, each with its own state from `init`. A worker can only run
+/// one source ahead of the sink, which keeps memory bounded by a few distance vectors. Stops at
+/// the first error returned by `sink`, or when a worker panics.
+pub(crate) fn for_each_source<S, I, F, K>(
+    sources: &[usize],
+    threads: usize,
+    init: I,
+    source: F,
+    mut sink: K,
+) -> Result<(), Error>
+where
+    I: Fn() -> S + Sync,
+    F: Fn(&mut S, usize, &mut Vec<ShortestPathLength>) + Sync,
+    K: FnMut(&[ShortestPathLength]) -> Result<(), Error>,
+{
+    if threads <= 1 {
+        let mut state = init();
+        let mut res = Vec::new();
+        for &src in sources {
+            res.clear();
+            source(&mut state, src, &mut res);
+            sink(&res)?;
+        }
+        return Ok(());
+    }
+
+    let init = &init;
+    let source = &source;
+    thread::scope(|scope| {
+        let (receivers, workers): (Vec<_>, Vec<_>) = (0..threads)
+            .map(|worker| {
+                let (sender, receiver) = mpsc::sync_channel(1);
+                let handle = scope.spawn(move || {
+                    let mut state = init();
+                    for &src in sources.iter().skip(worker).step_by(threads) {
+                        let mut res = Vec::new();
+                        source(&mut state, src, &mut res);
+                        if sender.send(res).is_err() {
+                            break;
+                        }
+                    }
+                });
+                (receiver, handle)
+            })
+            .unzip();
+        let mut result = Ok(());
+        for position in 0..sources.len() {
+            // a worker that panicked has hung up, and its panic is reported once joined
+            let res = match receivers[position % threads].recv() {
+                Ok(res) => res,
+                Err(_) => break,
+            };
+            if let Err(error) = sink(&res) {
+                result = Err(error);
+                break;
+            }
+        }
+        // dropping the receivers stops the workers still running
+        drop(receivers);
+        for worker in workers {
+            worker.join().map_err(panic_error)?;
+        }
+        result
+    })
+}
+
+// Joining the workers ourselves keeps the scope from panicking again with their panic.
+fn panic_error(panic: Box<dyn Any + Send>) -> Error {
+    let message = match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => match panic.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown panic".to_string(),
+        },
+    };
+    Error::Worker(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worker_panic_is_an_error() {
+        let sources: Vec<usize> = (0..8).collect();
+        let mut seen = Vec::new();
+        let result = for_each_source(
+            &sources,
+            2,
+            || (),
+            |_, src, res| {
+                if src == 5 {
+                    panic!("source {} failed", src);
+                }
+                res.push(ShortestPathLength {
+                    src,
+                    dst: src,
+                    length: 0,
+                });
+            },
+            |paths| {
+                seen.push(paths[0].src);
+                Ok(())
+            },
+        );
+        assert!(matches!(result, Err(Error::Worker(message)) if message == "source 5 failed"));
+        assert_eq!(seen, vec![0, 1, 2, 3, 4]);
+    }
+}
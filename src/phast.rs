@@ -0,0 +1,225 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use fast_paths::FastGraph;
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::parallel::for_each_source;
+use crate::{AllPairs, ShortestPathLength};
+
+const UNREACHED: usize = usize::MAX;
+
+// Mirror of the private layout of `fast_paths::FastGraph`, recovered by round-tripping it
+// through bincode. The layout is only known for the exact version pinned in Cargo.toml.
+#[derive(Deserialize)]
+struct FastGraphParts {
+    num_nodes: usize,
+    ranks: Vec<usize>,
+    edges_fwd: Vec<FastGraphEdge>,
+    first_edge_ids_fwd: Vec<usize>,
+    edges_bwd: Vec<FastGraphEdge>,
+    first_edge_ids_bwd: Vec<usize>,
+}
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct FastGraphEdge {
+    base_node: usize,
+    adj_node: usize,
+    weight: usize,
+    replaced_in_edge: usize,
+    replaced_out_edge: usize,
+}
+
+/// Contraction hierarchy laid out by rank for one-to-all queries (PHAST): an upward Dijkstra
+/// search from the source followed by a single downward sweep over all nodes in rank order.
+pub struct PhastGraph {
+    /// Can exceed the nodes in the hierarchy when trailing nodes have no edges
+    num_nodes: usize,
+    ranks: Vec<usize>,
+    up_offsets: Vec<usize>,
+    up_edges: Vec<(usize, usize)>,
+    down_offsets: Vec<usize>,
+    down_edges: Vec<(usize, usize)>,
+}
+
+// Edges of the node at each rank, with their adjacent node replaced by its rank.
+fn edges_by_rank(
+    edges: &[FastGraphEdge],
+    first_edge_ids: &[usize],
+    ranks: &[usize],
+) -> (Vec<usize>, Vec<(usize, usize)>) {
+    let offsets = first_edge_ids.to_vec();
+    let edges = edges
+        .iter()
+        .map(|edge| (ranks[edge.adj_node], edge.weight))
+        .collect();
+    (offsets, edges)
+}
+
+fn layout_error(reason: String) -> Error {
+    Error::Invalid(format!(
+        "unexpected layout of the contraction hierarchy: {}",
+        reason
+    ))
+}
+
+impl PhastGraph {
+    pub(crate) fn from_fast_graph(graph: &FastGraph, num_nodes: usize) -> Result<Self, Error> {
+        let parts: FastGraphParts = bincode::serialize(graph)
+            .and_then(|bytes| bincode::deserialize(&bytes))
+            .map_err(|e| layout_error(e.to_string()))?;
+        let ranked_nodes = parts.ranks.len();
+        if parts.num_nodes != ranked_nodes
+            || parts.first_edge_ids_fwd.len() != ranked_nodes + 1
+            || parts.first_edge_ids_bwd.len() != ranked_nodes + 1
+        {
+            return Err(layout_error("inconsistent node counts".to_string()));
+        }
+
+        let (up_offsets, up_edges) =
+            edges_by_rank(&parts.edges_fwd, &parts.first_edge_ids_fwd, &parts.ranks);
+        let (down_offsets, down_edges) =
+            edges_by_rank(&parts.edges_bwd, &parts.first_edge_ids_bwd, &parts.ranks);
+        Ok(PhastGraph {
+            num_nodes,
+            ranks: parts.ranks,
+            up_offsets,
+            up_edges,
+            down_offsets,
+            down_edges,
+        })
+    }
+
+    fn ranked_nodes(&self) -> usize {
+        self.ranks.len()
+    }
+
+    fn up(&self, rank: usize) -> &[(usize, usize)] {
+        &self.up_edges[self.up_offsets[rank]..self.up_offsets[rank + 1]]
+    }
+
+    fn down(&self, rank: usize) -> &[(usize, usize)] {
+        &self.down_edges[self.down_offsets[rank]..self.down_offsets[rank + 1]]
+    }
+}
+
+/// Reusable search state, distances are indexed by rank.
+struct PhastSearch {
+    distances: Vec<usize>,
+    heap: BinaryHeap<Reverse<(usize, usize)>>,
+}
+
+impl PhastSearch {
+    fn new(graph: &PhastGraph) -> Self {
+        PhastSearch {
+            distances: vec![UNREACHED; graph.ranked_nodes()],
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    /// Returns every node reachable from `src` (including itself) with its distance.
+    fn distances_from(&mut self, graph: &PhastGraph, src: usize) -> Vec<(usize, usize)> {
+        self.distances.fill(UNREACHED);
+        self.heap.clear();
+
+        let start = graph.ranks[src];
+        self.distances[start] = 0;
+        self.heap.push(Reverse((0, start)));
+        while let Some(Reverse((distance, rank))) = self.heap.pop() {
+            if distance > self.distances[rank] {
+                continue;
+            }
+            for &(adj, weight) in graph.up(rank) {
+                let next = distance + weight;
+                if next < self.distances[adj] {
+                    self.distances[adj] = next;
+                    self.heap.push(Reverse((next, adj)));
+                }
+            }
+        }
+
+        for rank in (0..graph.ranked_nodes()).rev() {
+            for &(adj, weight) in graph.down(rank) {
+                let from = self.distances[adj];
+                if from != UNREACHED && from + weight < self.distances[rank] {
+                    self.distances[rank] = from + weight;
+                }
+            }
+        }
+
+        graph
+            .ranks
+            .iter()
+            .enumerate()
+            .filter(|(_, &rank)| self.distances[rank] != UNREACHED)
+            .map(|(node, &rank)| (node, self.distances[rank]))
+            .collect()
+    }
+}
+
+impl AllPairs for PhastGraph {
+    fn num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    fn shortest_paths_from(
+        &self,
+        sources: &[usize],
+        threads: usize,
+        sink: &mut dyn FnMut(&[ShortestPathLength]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        for_each_source(
+            sources,
+            threads,
+            || PhastSearch::new(self),
+            |search, src, res| {
+                if src >= self.ranked_nodes() {
+                    res.push(ShortestPathLength {
+                        src,
+                        dst: src,
+                        length: 0,
+                    });
+                    return;
+                }
+                for (dst, length) in search.distances_from(self, src) {
+                    res.push(ShortestPathLength {
+                        src,
+                        dst,
+                        length: length as i64,
+                    })
+                }
+            },
+            sink,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::subset::SplitMix64;
+    use crate::tests::lengths;
+    use crate::Graph;
+
+    #[test]
+    fn same_lengths_as_dijkstra() {
+        let mut rng = SplitMix64(7);
+        for directed in [false, true] {
+            let mut builder = Graph::builder().directed(directed);
+            for _ in 0..600 {
+                let src = rng.below(200).to_string();
+                let dst = rng.below(200).to_string();
+                let weight = (1 + rng.below(100)) as f32;
+                builder.add_edge(&src, &dst, weight).unwrap();
+            }
+            // an isolated node, numbered after all the others
+            builder.add_node("isolated").unwrap();
+            let graph = builder.build();
+            assert_eq!(
+                lengths(&graph.fast_path().unwrap()),
+                lengths(&graph.dijkstra().unwrap())
+            );
+        }
+    }
+}
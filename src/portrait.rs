@@ -0,0 +1,348 @@
+use std::collections::BTreeSet;
+
+use crate::error::Error;
+use crate::{AllPairs, ShortestPathLength};
+
+// Unbinned portraits have a row per distance, so longer paths need bins.
+const MAX_UNBINNED_LENGTH: f64 = 65536.0;
+
+named_enum! {
+    #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+    pub enum Binning("binning") {
+        Quantile = "quantile",
+        Linear = "linear",
+    }
+}
+
+/// Network portrait `B[l][k]`: the number of nodes that have exactly `k` nodes at distance `l`.
+pub struct Portrait {
+    num_nodes: usize,
+    num_sources: usize,
+    shells: Vec<Vec<usize>>,
+}
+
+impl Portrait {
+    pub fn new(num_nodes: usize) -> Self {
+        Portrait {
+            num_nodes,
+            num_sources: 0,
+            shells: Vec::new(),
+        }
+    }
+
+    /// Computes the portrait of `graph`, binning path lengths when `edges` are given. Binned
+    /// portraits only compare lengths with each other, so they can stay scaled. Without bins,
+    /// every path must unscale to a whole distance of at least 1.
+    pub fn of_graph(
+        graph: &dyn AllPairs,
+        edges: Option<&[f64]>,
+        weight_scale: f64,
+        threads: usize,
+    ) -> Result<Self, Error> {
+        // a sampled portrait only counts the sources it searched
+        let mut portrait = Portrait::new(graph.num_sources());
+        graph.shortest_paths(threads, &mut |paths| {
+            if edges.is_none() {
+                check_unbinned(paths, weight_scale)?;
+            }
+            match edges {
+                Some(edges) => portrait.add_binned_paths(paths, edges),
+                None => portrait.add_paths(paths, weight_scale),
+            }
+            Ok(())
+        })?;
+        Ok(portrait)
+    }
+
+    /// Adds the distances from a single source to every other node it reaches.
+    pub fn add_source<I: IntoIterator<Item = usize>>(&mut self, lengths: I) {
+        // the source itself always sits at distance 0
+        let mut shell_sizes = vec![1];
+        for length in lengths {
+            if length >= shell_sizes.len() {
+                shell_sizes.resize(length + 1, 0);
+            }
+            shell_sizes[length] += 1;
+        }
+        self.add_shell_sizes(&shell_sizes);
+    }
+
+    /// Adds the distances from a single source, counting them into the bins delimited by `edges`.
+    /// The source itself is counted at distance 0.
+    pub fn add_binned_source<I: IntoIterator<Item = f64>>(&mut self, lengths: I, edges: &[f64]) {
+        let mut shell_sizes = vec![0; edges.len().saturating_sub(1)];
+        for length in std::iter::once(0.0).chain(lengths) {
+            if let Some(bin) = bin_index(edges, length) {
+                shell_sizes[bin] += 1;
+            }
+        }
+        self.add_shell_sizes(&shell_sizes);
+    }
+
+    pub fn add_shell_sizes(&mut self, shell_sizes: &[usize]) {
+        if shell_sizes.len() > self.shells.len() {
+            self.shells.resize(shell_sizes.len(), Vec::new());
+        }
+        for (l, &k) in shell_sizes.iter().enumerate() {
+            if k == 0 {
+                continue;
+            }
+            let row = &mut self.shells[l];
+            if k >= row.len() {
+                row.resize(k + 1, 0);
+            }
+            row[k] += 1;
+        }
+        self.num_sources += 1;
+    }
+
+    /// Adds the paths of whole sources to an unweighted portrait: path lengths are unscaled by
+    /// `weight_scale` and rounded to integer distances.
+    pub fn add_paths(&mut self, paths: &[ShortestPathLength], weight_scale: f64) {
+        for source_paths in paths.chunk_by(|a, b| a.src == b.src) {
+            let src = source_paths[0].src;
+            self.add_source(
+                source_paths
+                    .iter()
+                    .filter(|path| path.dst != src)
+                    .map(|path| (path.length as f64 / weight_scale).round() as usize),
+            );
+        }
+    }
+
+    /// Adds the paths of whole sources to a weighted portrait, whose rows are path length bins
+    /// instead of integer distances.
+    pub fn add_binned_paths(&mut self, paths: &[ShortestPathLength], edges: &[f64]) {
+        for source_paths in paths.chunk_by(|a, b| a.src == b.src) {
+            let src = source_paths[0].src;
+            self.add_binned_source(
+                source_paths
+                    .iter()
+                    .filter(|path| path.dst != src)
+                    .map(|path| path.length as f64),
+                edges,
+            );
+        }
+    }
+
+    /// Returns the rectangular `B` matrix, rows indexed by distance and columns by shell size.
+    pub fn matrix(&self) -> Vec<Vec<usize>> {
+        // nodes that never showed up as a source are isolated
+        let missing = self.num_nodes.saturating_sub(self.num_sources);
+        let mut shells = self.shells.clone();
+        if shells.is_empty() {
+            shells.push(Vec::new());
+        }
+        if missing > 0 {
+            if shells[0].len() < 2 {
+                shells[0].resize(2, 0);
+            }
+            shells[0][1] += missing;
+        }
+
+        let width = shells.iter().map(|row| row.len()).max().unwrap_or(0).max(2);
+        shells
+            .into_iter()
+            .map(|mut row| {
+                row.resize(width, 0);
+                let reached: usize = row[1..].iter().sum();
+                row[0] = self.num_nodes.saturating_sub(reached);
+                row
+            })
+            .collect()
+    }
+}
+
+// Distances index the rows of unbinned portraits, so paths must be a whole number of unit
+// edges long. Anything else would be rounded into a neighbouring row, or into the source's
+// row 0.
+fn check_unbinned(paths: &[ShortestPathLength], weight_scale: f64) -> Result<(), Error> {
+    for path in paths.iter().filter(|path| path.src != path.dst) {
+        let length = path.length as f64 / weight_scale;
+        let rounded = length.round();
+        let reason = if length < 0.0 {
+            "is negative, which needs bins"
+        } else if rounded > MAX_UNBINNED_LENGTH {
+            "needs too many rows for an unbinned portrait, use bins"
+        } else if rounded < 1.0 {
+            "rounds to the source's own distance 0 in an unbinned portrait, use bins"
+        } else if (length - rounded).abs() > 1e-9 * rounded {
+            "isn't a whole number of edges for an unbinned portrait, use bins"
+        } else {
+            continue;
+        };
+        return Err(Error::Invalid(format!("path length {} {}", length, reason)));
+    }
+    Ok(())
+}
+
+/// Distinct path lengths seen so far, including the zero distance of every source.
+#[derive(Default)]
+pub struct ObservedLengths(BTreeSet<i64>);
+
+impl ObservedLengths {
+    pub fn add_paths(&mut self, paths: &[ShortestPathLength]) {
+        self.0.insert(0);
+        self.0.extend(paths.iter().map(|path| path.length));
+    }
+
+    /// The observed lengths in ascending order.
+    pub fn lengths(&self) -> Vec<f64> {
+        self.0.iter().map(|&length| length as f64).collect()
+    }
+}
+
+/// Computes `bins + 1` bin edges spanning the sorted `lengths`, either at evenly spaced
+/// percentiles (as in the weighted portrait of Bagrow & Bollt) or evenly spaced values.
+pub fn bin_edges(lengths: &[f64], bins: usize, binning: Binning) -> Vec<f64> {
+    let (min, max) = match (lengths.first(), lengths.last()) {
+        (Some(&min), Some(&max)) => (min, max),
+        _ => return vec![0.0; bins + 1],
+    };
+    (0..=bins)
+        .map(|i| {
+            let fraction = i as f64 / bins as f64;
+            match binning {
+                Binning::Linear => min + (max - min) * fraction,
+                Binning::Quantile => {
+                    let position = fraction * (lengths.len() - 1) as f64;
+                    let lower = position.floor() as usize;
+                    let upper = position.ceil() as usize;
+                    lengths[lower] + (lengths[upper] - lengths[lower]) * (position - lower as f64)
+                }
+            }
+        })
+        .collect()
+}
+
+/// Bin edges shared by the portraits of all `graphs`, so they can be compared. Binning needs
+/// every path length up front, so this takes a first pass over their shortest paths.
+pub fn shared_bin_edges(
+    graphs: &[&dyn AllPairs],
+    bins: usize,
+    binning: Binning,
+    threads: usize,
+) -> Result<Vec<f64>, Error> {
+    let mut observed = ObservedLengths::default();
+    for graph in graphs {
+        graph.shortest_paths(threads, &mut |paths| {
+            observed.add_paths(paths);
+            Ok(())
+        })?;
+    }
+    Ok(bin_edges(&observed.lengths(), bins, binning))
+}
+
+// Bins are half-open except for the last one, which also includes its upper edge.
+fn bin_index(edges: &[f64], length: f64) -> Option<usize> {
+    let last = edges.len().checked_sub(1)?;
+    if last == 0 || length < edges[0] || length > edges[last] {
+        return None;
+    }
+    Some((edges.partition_point(|&edge| edge <= length) - 1).min(last - 1))
+}
+
+fn distribution(matrix: &[Vec<usize>], rows: usize, width: usize) -> Vec<f64> {
+    let mut weights = Vec::with_capacity(rows * width);
+    for l in 0..rows {
+        for k in 0..width {
+            let count = matrix
+                .get(l)
+                .and_then(|row| row.get(k))
+                .copied()
+                .unwrap_or(0);
+            weights.push((k * count) as f64);
+        }
+    }
+    let total: f64 = weights.iter().sum();
+    if total > 0.0 {
+        weights.iter_mut().for_each(|weight| *weight /= total);
+    }
+    weights
+}
+
+fn kullback_leibler(p: &[f64], m: &[f64]) -> f64 {
+    p.iter()
+        .zip(m)
+        .filter(|(&p, _)| p > 0.0)
+        .map(|(&p, &m)| p * (p / m).log2())
+        .sum()
+}
+
+/// Jensen-Shannon divergence between the shortest path distributions of two portraits
+/// (Bagrow & Bollt, 2019). Ranges from 0 for identical portraits to 1.
+pub fn portrait_divergence(first: &Portrait, second: &Portrait) -> f64 {
+    let first = first.matrix();
+    let second = second.matrix();
+    let rows = first.len().max(second.len());
+    let width = first[0].len().max(second[0].len());
+
+    let p = distribution(&first, rows, width);
+    let q = distribution(&second, rows, width);
+    let m: Vec<f64> = p.iter().zip(&q).map(|(p, q)| 0.5 * (p + q)).collect();
+    0.5 * (kullback_leibler(&p, &m) + kullback_leibler(&q, &m))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Graph;
+
+    fn portrait(edges: &[(&str, &str)]) -> Portrait {
+        let mut builder = Graph::builder();
+        for &(src, dst) in edges {
+            builder.add_edge(src, dst, 1.0).unwrap();
+        }
+        let graph = builder.build();
+        Portrait::of_graph(&graph.dijkstra().unwrap(), None, graph.weight_scale(), 1).unwrap()
+    }
+
+    #[test]
+    fn portrait_of_path() {
+        let path = portrait(&[("a", "b"), ("b", "c")]);
+        assert_eq!(
+            path.matrix(),
+            vec![vec![0, 3, 0], vec![0, 2, 1], vec![1, 2, 0]]
+        );
+    }
+
+    #[test]
+    fn unbinned_portrait_of_long_paths() {
+        let mut builder = Graph::builder();
+        builder.add_edge("a", "b", 50_000_000.0).unwrap();
+        let graph = builder.build();
+        assert!(Portrait::of_graph(&graph.dijkstra().unwrap(), None, 1.0, 1).is_err());
+        let edges = [0.0, 25_000_000.0, 50_000_000.0];
+        let portrait =
+            Portrait::of_graph(&graph.dijkstra().unwrap(), Some(&edges), 1.0, 1).unwrap();
+        assert_eq!(portrait.matrix(), vec![vec![0, 2], vec![0, 2]]);
+    }
+
+    #[test]
+    fn unbinned_portrait_of_fractional_lengths() {
+        for (weights, weight_scale) in [([0.4, 0.4], 10.0), ([0.0, 1.0], 1.0), ([1.5, 1.0], 10.0)] {
+            let mut builder = Graph::builder().weight_scale(weight_scale);
+            builder.add_edge("a", "b", weights[0]).unwrap();
+            builder.add_edge("b", "c", weights[1]).unwrap();
+            let graph = builder.build();
+            let error = Portrait::of_graph(&graph.dijkstra().unwrap(), None, weight_scale, 1)
+                .err()
+                .unwrap();
+            assert!(error.to_string().ends_with("use bins"), "{}", error);
+        }
+        let mut builder = Graph::builder().weight_scale(10.0);
+        builder.add_edge("a", "b", 2.0).unwrap();
+        let graph = builder.build();
+        let portrait = Portrait::of_graph(&graph.dijkstra().unwrap(), None, 10.0, 1).unwrap();
+        assert_eq!(portrait.matrix(), vec![vec![0, 2], vec![2, 0], vec![0, 2]]);
+    }
+
+    #[test]
+    fn divergence_of_path_and_triangle() {
+        let path = portrait(&[("a", "b"), ("b", "c")]);
+        let triangle = portrait(&[("a", "b"), ("b", "c"), ("c", "a")]);
+        assert_eq!(portrait_divergence(&path, &path), 0.0);
+        let divergence = portrait_divergence(&path, &triangle);
+        assert!((divergence - 0.3060986).abs() < 1e-6, "{}", divergence);
+    }
+}
@@ -0,0 +1,246 @@
+//! Prepared graphs: a graph saved together with its contraction hierarchy, so repeated queries
+//! skip `fast_paths::prepare`. The file records a checksum of the edge list it was prepared
+//! from and is refused once that file changes.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+use fast_paths::FastGraph;
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::labels::{IndexedEdge, NodeLabels};
+use crate::stream;
+use crate::{EdgeSummary, Graph};
+
+const MAGIC: &[u8; 8] = b"RSPGRAPH";
+
+// Bumped whenever the layout changes, so older files are refused instead of misread. After it
+// come the source path and checksum, the labels, the edges as (src, dst, weight), whether the
+// graph is directed, its weight scale and the contraction hierarchy.
+const VERSION: u64 = 2;
+
+// Written as a tuple of the same fields, which bincode lays out alike.
+#[derive(Deserialize)]
+struct PreparedGraph {
+    source: String,
+    checksum: u64,
+    labels: Vec<String>,
+    edges: Vec<(usize, usize, f32)>,
+    directed: bool,
+    weight_scale: f64,
+    fast_graph: FastGraph,
+}
+
+/// FNV-1a hash of the raw bytes of the file at `path`.
+fn checksum(path: &str) -> io::Result<u64> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut buf = [0; 64 * 1024];
+    loop {
+        let len = reader.read(&mut buf)?;
+        if len == 0 {
+            return Ok(hash);
+        }
+        for &byte in &buf[..len] {
+            hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+/// Whether `path` is a prepared graph rather than an edge list. Only plain files can be.
+pub fn is_prepared(path: &str) -> bool {
+    if path == "-" || stream::uncompressed_path(path) != path {
+        return false;
+    }
+    let mut magic = [0; 8];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok_and(|_| &magic == MAGIC)
+}
+
+fn invalid(path: &str, message: String) -> Error {
+    Error::Parse {
+        path: path.to_string(),
+        line: None,
+        column: None,
+        message,
+    }
+}
+
+impl Graph {
+    /// Saves the graph and its contraction hierarchy to `output`, which must be a plain file,
+    /// along with a checksum of `source`, the edge list it was read from.
+    pub fn write_prepared(&self, output: &str, source: &str) -> Result<(), Error> {
+        if output == "-" || stream::uncompressed_path(output) != output {
+            return Err(Error::Invalid(
+                "prepared graphs can only be written to an uncompressed file".to_string(),
+            ));
+        }
+        if source == "-" {
+            return Err(Error::Invalid(
+                "prepared graphs need an edge list file to check against, not stdin".to_string(),
+            ));
+        }
+        let source_path = fs::canonicalize(source).map_err(|e| Error::io(source, e))?;
+        let source_path = source_path.to_string_lossy().into_owned();
+        let source_checksum = checksum(source).map_err(|e| Error::io(source, e))?;
+
+        let prepared;
+        let fast_graph = match &self.fast_graph {
+            Some(fast_graph) => fast_graph,
+            None => {
+                prepared = self.prepare_fast_graph()?;
+                &prepared
+            }
+        };
+        let labels: Vec<&str> = (0..self.labels.len())
+            .map(|index| self.labels.label(index))
+            .collect();
+        let edges: Vec<(usize, usize, f32)> = self
+            .edges
+            .iter()
+            .map(|edge| (edge.src, edge.dst, edge.weight))
+            .collect();
+        let layout = (
+            source_path,
+            source_checksum,
+            labels,
+            edges,
+            self.directed,
+            self.weight_scale,
+            fast_graph,
+        );
+
+        let file = File::create(output).map_err(|e| Error::io(output, e))?;
+        let mut writer = BufWriter::new(file);
+        writer
+            .write_all(MAGIC)
+            .and_then(|_| writer.write_all(&VERSION.to_le_bytes()))
+            .map_err(|e| Error::io(output, e))?;
+        bincode::serialize_into(&mut writer, &layout)
+            .map_err(|e| invalid(output, format!("could not write the prepared graph: {}", e)))?;
+        writer.flush().map_err(|e| Error::io(output, e))
+    }
+
+    /// Loads a graph saved by [`Graph::write_prepared`], failing when the edge list it was
+    /// prepared from has changed since.
+    pub fn read_prepared(path: &str) -> Result<Graph, Error> {
+        let file = File::open(path).map_err(|e| Error::io(path, e))?;
+        let mut reader = BufReader::new(file);
+        let mut magic = [0; 8];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|e| Error::io(path, e))?;
+        if &magic != MAGIC {
+            return Err(invalid(path, "not a prepared graph".to_string()));
+        }
+        let mut version = [0; 8];
+        reader
+            .read_exact(&mut version)
+            .map_err(|e| Error::io(path, e))?;
+        if u64::from_le_bytes(version) != VERSION {
+            return Err(invalid(
+                path,
+                "prepared by another version, prepare it again".to_string(),
+            ));
+        }
+
+        let prepared: PreparedGraph = bincode::deserialize_from(reader)
+            .map_err(|e| invalid(path, format!("invalid prepared graph: {}", e)))?;
+        let source = &prepared.source;
+        let current = checksum(source).map_err(|e| Error::io(source, e))?;
+        if current != prepared.checksum {
+            return Err(invalid(
+                path,
+                format!("stale, {} has changed since it was prepared", source),
+            ));
+        }
+
+        let mut labels = NodeLabels::default();
+        for label in prepared.labels {
+            labels.intern(label);
+        }
+        let edges = prepared
+            .edges
+            .into_iter()
+            .map(|(src, dst, weight)| IndexedEdge { src, dst, weight })
+            .collect();
+        Ok(Graph {
+            labels,
+            edges,
+            directed: prepared.directed,
+            weight_scale: prepared.weight_scale,
+            summary: EdgeSummary::default(),
+            fast_graph: Some(prepared.fast_graph),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{lengths, temp_path};
+    use crate::EdgeListOptions;
+
+    fn graph(path: &str) -> Graph {
+        let mut builder = Graph::builder().directed(true);
+        builder
+            .read_edges(path, &EdgeListOptions::default())
+            .unwrap();
+        builder.build()
+    }
+
+    #[test]
+    fn prepared_graph_round_trip() {
+        let source = temp_path("round-trip.csv");
+        let output = temp_path("round-trip.prep");
+        fs::write(&source, "a,b,1\nb,c,2\nc,a,4\na,d,3\n").unwrap();
+        let graph = graph(&source);
+        graph.write_prepared(&output, &source).unwrap();
+        assert!(is_prepared(&output));
+        assert!(!is_prepared(&source));
+
+        let prepared = Graph::read_prepared(&output).unwrap();
+        assert_eq!(prepared.labels().label(3), "d");
+        assert!(prepared.directed);
+        assert_eq!(
+            lengths(&prepared.fast_path().unwrap()),
+            lengths(&graph.dijkstra().unwrap())
+        );
+        assert_eq!(
+            lengths(&prepared.dijkstra().unwrap()),
+            lengths(&graph.dijkstra().unwrap())
+        );
+
+        fs::write(&source, "a,b,1\nb,c,2\nc,a,5\na,d,3\n").unwrap();
+        let error = Graph::read_prepared(&output).err().unwrap();
+        assert!(error.to_string().contains("stale"), "{}", error);
+        fs::remove_file(source).unwrap();
+        fs::remove_file(output).unwrap();
+    }
+
+    #[test]
+    fn wrong_magic_or_version() {
+        let source = temp_path("version.csv");
+        let output = temp_path("version.prep");
+        fs::write(&source, "a,b,1\n").unwrap();
+        graph(&source).write_prepared(&output, &source).unwrap();
+        let mut bytes = fs::read(&output).unwrap();
+
+        bytes[8..16].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        fs::write(&output, &bytes).unwrap();
+        let error = Graph::read_prepared(&output).err().unwrap();
+        assert!(error
+            .to_string()
+            .ends_with("prepared by another version, prepare it again"));
+
+        bytes[..8].copy_from_slice(b"NOTAGRPH");
+        fs::write(&output, &bytes).unwrap();
+        assert!(!is_prepared(&output));
+        let error = Graph::read_prepared(&output).err().unwrap();
+        assert!(error.to_string().ends_with("not a prepared graph"));
+        fs::remove_file(source).unwrap();
+        fs::remove_file(output).unwrap();
+    }
+}
@@ -0,0 +1,163 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+
+#[derive(Clone, Copy)]
+enum Compression {
+    Gzip,
+    Zstd,
+}
+
+/// Paths ending in one of these are (de)compressed in that format.
+const EXTENSIONS: [(&str, Compression); 2] =
+    [(".gz", Compression::Gzip), (".zst", Compression::Zstd)];
+
+fn compression(path: &str) -> Option<Compression> {
+    EXTENSIONS
+        .iter()
+        .find(|(extension, _)| path.ends_with(extension))
+        .map(|&(_, compression)| compression)
+}
+
+/// The path without its compression extension, to find out what format the contents are in.
+pub(crate) fn uncompressed_path(path: &str) -> &str {
+    EXTENSIONS
+        .iter()
+        .find_map(|(extension, _)| path.strip_suffix(extension))
+        .unwrap_or(path)
+}
+
+/// Opens `path` for reading, decompressing it when it ends in `.gz` or `.zst`. `-` is stdin.
+/// A truncated or corrupt compressed file is a read error rather than a short read.
+pub(crate) fn open(path: &str) -> io::Result<Box<dyn Read>> {
+    if path == "-" {
+        return Ok(Box::new(io::stdin()));
+    }
+    let file = File::open(path)?;
+    Ok(match compression(path) {
+        Some(Compression::Gzip) => Box::new(MultiGzDecoder::new(BufReader::new(file))),
+        Some(Compression::Zstd) => Box::new(zstd::Decoder::new(file)?),
+        None => Box::new(file),
+    })
+}
+
+pub(crate) fn read_to_string(path: &str) -> io::Result<String> {
+    let mut text = String::new();
+    open(path)?.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+enum Sink {
+    Plain(Box<dyn Write>),
+    Gzip(GzEncoder<File>),
+    Zstd(zstd::Encoder<'static, File>),
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Sink::Plain(writer) => writer.write(buf),
+            Sink::Gzip(encoder) => encoder.write(buf),
+            Sink::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Sink::Plain(writer) => writer.flush(),
+            Sink::Gzip(encoder) => encoder.flush(),
+            Sink::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// A file being written, compressed when its path ends in `.gz` or `.zst`. `-` is stdout.
+/// Call `finish` once everything is written, so the compressed stream is completed.
+pub(crate) struct Output {
+    writer: BufWriter<Sink>,
+}
+
+impl Output {
+    pub(crate) fn create(path: &str) -> io::Result<Self> {
+        if path == "-" {
+            return Ok(Output {
+                writer: BufWriter::new(Sink::Plain(Box::new(io::stdout()))),
+            });
+        }
+        let file = File::create(path)?;
+        let sink = match compression(path) {
+            Some(Compression::Gzip) => {
+                Sink::Gzip(GzEncoder::new(file, flate2::Compression::default()))
+            }
+            Some(Compression::Zstd) => Sink::Zstd(zstd::Encoder::new(file, 0)?),
+            None => Sink::Plain(Box::new(file)),
+        };
+        Ok(Output {
+            writer: BufWriter::new(sink),
+        })
+    }
+
+    pub(crate) fn finish(self) -> io::Result<()> {
+        match self.writer.into_inner().map_err(|e| e.into_error())? {
+            Sink::Plain(mut writer) => writer.flush(),
+            Sink::Gzip(encoder) => encoder.finish().map(drop),
+            Sink::Zstd(encoder) => encoder.finish().map(drop),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::temp_path;
+
+    fn write(path: &str, text: &str) {
+        let mut output = Output::create(path).unwrap();
+        output.write_all(text.as_bytes()).unwrap();
+        output.finish().unwrap();
+    }
+
+    #[test]
+    fn compressed_round_trip() {
+        let text = "a,b,1\n".repeat(10_000);
+        for extension in ["", ".gz", ".zst"] {
+            let path = temp_path(&format!("round-trip.csv{}", extension));
+            write(&path, &text);
+            assert_eq!(read_to_string(&path).unwrap(), text, "{}", extension);
+            if !extension.is_empty() {
+                assert!(std::fs::metadata(&path).unwrap().len() < text.len() as u64);
+                // a truncated stream is an error, not a shorter file
+                let bytes = std::fs::read(&path).unwrap();
+                std::fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+                assert!(read_to_string(&path).is_err(), "{}", extension);
+            }
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn gzip_members_are_concatenated() {
+        let first = temp_path("first.csv.gz");
+        let second = temp_path("second.csv.gz");
+        write(&first, "a,b,1\n");
+        write(&second, "b,c,2\n");
+        let mut bytes = std::fs::read(&first).unwrap();
+        bytes.extend(std::fs::read(&second).unwrap());
+        std::fs::write(&first, bytes).unwrap();
+        assert_eq!(read_to_string(&first).unwrap(), "a,b,1\nb,c,2\n");
+        std::fs::remove_file(first).unwrap();
+        std::fs::remove_file(second).unwrap();
+    }
+}
@@ -0,0 +1,199 @@
+use crate::error::Error;
+use crate::{AllPairs, ShortestPathLength};
+
+/// A backend restricted to some sources and targets. Only the sources are searched, in the
+/// order given, and only the paths to targets are passed on.
+pub struct Subset<'a> {
+    backend: &'a dyn AllPairs,
+    sources: Vec<usize>,
+    targets: Option<Targets>,
+}
+
+/// The nodes paths are wanted to, so a search can stop once it has settled all of them.
+pub struct Targets {
+    included: Vec<bool>,
+    nodes: Vec<usize>,
+}
+
+impl Targets {
+    /// Repeated nodes count once.
+    pub fn new(num_nodes: usize, targets: &[usize]) -> Self {
+        let mut included = vec![false; num_nodes];
+        for &target in targets {
+            included[target] = true;
+        }
+        let nodes = (0..num_nodes).filter(|&node| included[node]).collect();
+        Targets { included, nodes }
+    }
+
+    pub fn contains(&self, node: usize) -> bool {
+        self.included[node]
+    }
+
+    /// The distinct target nodes in index order.
+    pub fn nodes(&self) -> &[usize] {
+        &self.nodes
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl<'a> Subset<'a> {
+    /// Keeps every source or target when `sources` or `targets` is `None`.
+    pub fn new(
+        backend: &'a dyn AllPairs,
+        sources: Option<Vec<usize>>,
+        targets: Option<&[usize]>,
+    ) -> Self {
+        let num_nodes = backend.num_nodes();
+        Subset {
+            backend,
+            sources: sources.unwrap_or_else(|| (0..num_nodes).collect()),
+            targets: targets.map(|targets| Targets::new(num_nodes, targets)),
+        }
+    }
+}
+
+impl AllPairs for Subset<'_> {
+    fn num_nodes(&self) -> usize {
+        self.backend.num_nodes()
+    }
+
+    fn num_sources(&self) -> usize {
+        self.sources.len()
+    }
+
+    fn sources(&self) -> Vec<usize> {
+        self.sources.clone()
+    }
+
+    fn targets(&self) -> Vec<usize> {
+        match &self.targets {
+            Some(targets) => targets.nodes().to_vec(),
+            None => self.backend.targets(),
+        }
+    }
+
+    fn shortest_paths_from(
+        &self,
+        sources: &[usize],
+        threads: usize,
+        sink: &mut dyn FnMut(&[ShortestPathLength]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        match &self.targets {
+            Some(targets) => self
+                .backend
+                .shortest_paths_to(sources, targets, threads, sink),
+            None => self.backend.shortest_paths_from(sources, threads, sink),
+        }
+    }
+
+    fn shortest_paths(
+        &self,
+        threads: usize,
+        sink: &mut dyn FnMut(&[ShortestPathLength]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        self.shortest_paths_from(&self.sources, threads, sink)
+    }
+}
+
+// SplitMix64, which is plenty for picking sources and keeps samples the same on every
+// platform for a given seed.
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in `0..bound`, rejecting the top of the range that would bias the modulo.
+    pub(crate) fn below(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+/// Picks `count` distinct nodes out of `num_nodes` at random, in ascending order. The same
+/// `seed` always picks the same nodes.
+pub fn sample_sources(num_nodes: usize, count: usize, seed: u64) -> Vec<usize> {
+    let mut rng = SplitMix64(seed);
+    let mut nodes: Vec<usize> = (0..num_nodes).collect();
+    let count = count.min(num_nodes);
+    // partial Fisher-Yates shuffle of the first `count` nodes
+    for i in 0..count {
+        let j = i + rng.below((num_nodes - i) as u64) as usize;
+        nodes.swap(i, j);
+    }
+    nodes.truncate(count);
+    nodes.sort_unstable();
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::lengths;
+    use crate::Graph;
+
+    #[test]
+    fn searches_stopping_at_targets() {
+        let mut rng = SplitMix64(1);
+        let mut builder = Graph::builder().directed(true);
+        for _ in 0..300 {
+            let src = rng.below(100).to_string();
+            let dst = rng.below(100).to_string();
+            builder
+                .add_edge(&src, &dst, (1 + rng.below(9)) as f32)
+                .unwrap();
+        }
+        let graph = builder.build();
+        let mut targets = sample_sources(graph.num_nodes(), 5, 2);
+        targets.push(targets[0]);
+        let (dijkstra, bfs, floyd_warshall) = (
+            graph.dijkstra().unwrap(),
+            graph.bfs(),
+            graph.floyd_warshall().unwrap(),
+        );
+        let backends: [&dyn AllPairs; 3] = [&dijkstra, &bfs, &floyd_warshall];
+        for backend in backends {
+            let expected: Vec<_> = lengths(backend)
+                .into_iter()
+                .filter(|path| targets.contains(&path.1))
+                .collect();
+            assert_eq!(
+                lengths(&Subset::new(backend, None, Some(&targets))),
+                expected
+            );
+        }
+        let none = Subset::new(backends[0], None, Some(&[]));
+        assert!(lengths(&none).is_empty());
+    }
+
+    #[test]
+    fn samples_are_deterministic() {
+        let sample = sample_sources(1000, 10, 42);
+        assert_eq!(sample, sample_sources(1000, 10, 42));
+        assert_ne!(sample, sample_sources(1000, 10, 43));
+        assert_eq!(sample.len(), 10);
+        assert!(sample.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(sample.iter().all(|&node| node < 1000));
+        // pins the generator, so samples stay the same across releases and platforms
+        assert_eq!(SplitMix64(0).next(), 0xe220a8397b1dcdaf);
+        assert_eq!(sample_sources(5, 10, 7), vec![0, 1, 2, 3, 4]);
+    }
+}
@@ -0,0 +1,10 @@
+# a directed graph
+graph [
+  directed 1
+  node [ id 1 label "first node" ]
+  node [ id 2 ]
+  node [ id 3 label "third" ]
+  # weights come from either key
+  edge [ source 1 target 2 weight 3 ]
+  edge [ source 2 target 3 value 4.5 ]
+]
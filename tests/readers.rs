@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use rust_shortest_path::{AllPairs, Column, EdgeListOptions, Graph};
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+// Shortest path lengths between distinct nodes, by label.
+fn lengths(name: &str, options: &EdgeListOptions) -> BTreeMap<(String, String), i64> {
+    let mut builder = Graph::builder().directed(true);
+    builder.read_edges(&fixture(name), options).unwrap();
+    let graph = builder.build();
+    let labels = graph.labels();
+    let mut lengths = BTreeMap::new();
+    graph
+        .dijkstra()
+        .unwrap()
+        .shortest_paths(1, &mut |paths| {
+            for path in paths.iter().filter(|path| path.src != path.dst) {
+                let src = labels.label(path.src).to_string();
+                let dst = labels.label(path.dst).to_string();
+                lengths.insert((src, dst), path.length);
+            }
+            Ok(())
+        })
+        .unwrap();
+    lengths
+}
+
+fn expected(lengths: &[(&str, &str, i64)]) -> BTreeMap<(String, String), i64> {
+    lengths
+        .iter()
+        .map(|&(src, dst, length)| ((src.to_string(), dst.to_string()), length))
+        .collect()
+}
+
+fn nodes(name: &str) -> Vec<String> {
+    let mut builder = Graph::builder();
+    builder
+        .read_edges(&fixture(name), &EdgeListOptions::default())
+        .unwrap();
+    let graph = builder.build();
+    let labels = graph.labels();
+    (0..labels.len())
+        .map(|index| labels.label(index).to_string())
+        .collect()
+}
+
+#[test]
+fn graphml() {
+    assert_eq!(
+        lengths("weighted.graphml", &EdgeListOptions::default()),
+        expected(&[
+            ("a&b", "cA", 2),
+            ("a&b", "d", 7),
+            ("cA", "a&b", 2),
+            ("cA", "d", 5),
+        ])
+    );
+    assert!(nodes("weighted.graphml").contains(&"lonely".to_string()));
+}
+
+#[test]
+fn gml() {
+    assert_eq!(
+        lengths("weighted.gml", &EdgeListOptions::default()),
+        expected(&[
+            ("first node", "2", 3),
+            ("first node", "third", 8),
+            ("2", "third", 5),
+        ])
+    );
+}
+
+#[test]
+fn pajek() {
+    assert_eq!(
+        lengths("mixed.net", &EdgeListOptions::default()),
+        expected(&[("a", "b", 2), ("a", "c", 7), ("b", "c", 5), ("c", "b", 5),])
+    );
+    assert!(nodes("mixed.net").contains(&"4".to_string()));
+}
+
+#[test]
+fn matrix_market() {
+    assert_eq!(
+        lengths("symmetric.mtx", &EdgeListOptions::default()),
+        expected(&[
+            ("1", "2", 1),
+            ("1", "3", 3),
+            ("2", "1", 1),
+            ("2", "3", 2),
+            ("3", "1", 3),
+            ("3", "2", 2),
+        ])
+    );
+    assert_eq!(
+        lengths("pattern.mtx", &EdgeListOptions::default()),
+        expected(&[("1", "2", 1), ("1", "3", 2), ("2", "3", 1)])
+    );
+}
+
+#[test]
+fn csv() {
+    let options = EdgeListOptions {
+        has_header: true,
+        source_column: Column::Name("to".to_string()),
+        target_column: Column::Name("from".to_string()),
+        weight_column: Column::Name("cost".to_string()),
+        comment: Some('#'),
+        ..EdgeListOptions::default()
+    };
+    assert_eq!(
+        lengths("header.csv", &options),
+        expected(&[("b", "a", 1), ("c\nd", "b", 2), ("c\nd", "a", 3)])
+    );
+}
+
+#[test]
+fn csv_line_numbers() {
+    let options = EdgeListOptions {
+        comment: Some('#'),
+        ..EdgeListOptions::default()
+    };
+    let mut builder = Graph::builder();
+    let error = builder
+        .read_edges(&fixture("bad_weight.csv"), &options)
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .ends_with("bad_weight.csv:6: column 3: invalid weight \"x\""));
+}